@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use leonardo_engine::algebra::vec3::{Color, Point3, Vec3};
+use leonardo_engine::graphics::bvh::BvhNode;
+use leonardo_engine::graphics::hit::{HitRecord, Hittable, HittableStore};
+use leonardo_engine::graphics::material::Lambertian;
+use leonardo_engine::graphics::ray::Ray;
+use leonardo_engine::graphics::sphere::Sphere;
+
+fn sphere_field(count: usize, rng: &mut StdRng) -> HittableStore {
+    let mut world = HittableStore::new();
+    let side = (count as f32).sqrt().ceil() as usize;
+    for i in 0..count {
+        let center = Point3::new(
+            (i % side) as f32 + rng.gen_range(0.0, 0.9),
+            rng.gen_range(0.0, 2.0),
+            (i / side) as f32 + rng.gen_range(0.0, 0.9),
+        );
+        world.store(Box::new(Sphere::new(
+            center,
+            0.2,
+            Some(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))),
+        )));
+    }
+    world
+}
+
+fn random_rays(count: usize, extent: f32, rng: &mut StdRng) -> Vec<Ray> {
+    (0..count)
+        .map(|_| {
+            let origin = Point3::new(rng.gen_range(-2.0, extent + 2.0), 5.0, rng.gen_range(-2.0, extent + 2.0));
+            let target = Point3::new(rng.gen_range(0.0, extent), 0.0, rng.gen_range(0.0, extent));
+            Ray::new(origin, Vec3::unit_vector(target - origin))
+        })
+        .collect()
+}
+
+fn trace_all(world: &dyn Hittable, rays: &[Ray]) -> usize {
+    let hit_record = HitRecord::new_def();
+    rays.iter()
+        .filter(|ray| world.hit(**ray, 0.001, f32::MAX, &hit_record).is_some())
+        .count()
+}
+
+fn bench_closest_hit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("closest_hit");
+    for count in [484usize, 4096, 16384].iter() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let extent = (*count as f32).sqrt();
+        let rays = random_rays(1024, extent, &mut rng);
+        let linear = sphere_field(*count, &mut StdRng::seed_from_u64(42));
+        let bvh = BvhNode::new(sphere_field(*count, &mut StdRng::seed_from_u64(42)), 0.0, 0.0);
+        group.bench_with_input(BenchmarkId::new("linear", count), &rays, |b, rays| {
+            b.iter(|| trace_all(black_box(&linear), rays))
+        });
+        group.bench_with_input(BenchmarkId::new("bvh", count), &rays, |b, rays| {
+            b.iter(|| trace_all(black_box(&bvh), rays))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_closest_hit);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+pub struct Distribution1D {
+    function: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(function: Vec<f32>) -> Distribution1D {
+        let n = function.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + function[i].max(0.0) / n as f32;
+        }
+        let integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate().skip(1) {
+            *value = if integral > 0.0 { *value / integral } else { i as f32 / n as f32 };
+        }
+        Distribution1D {
+            function,
+            cdf,
+            integral,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.function.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.function.is_empty()
+    }
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+    fn density(&self, offset: usize) -> f32 {
+        if self.integral > 0.0 { self.function[offset].max(0.0) / self.integral } else { 1.0 }
+    }
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let n = self.len();
+        let offset = self.cdf.partition_point(|&value| value <= u).saturating_sub(1).min(n - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x = ((offset as f32 + du) / n as f32).min(1.0 - f32::EPSILON);
+        (x, self.density(offset), offset)
+    }
+    pub fn pdf(&self, x: f32) -> f32 {
+        let n = self.len();
+        self.density(((x * n as f32) as usize).min(n - 1))
+    }
+}
+
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(function: &[f32], width: usize, height: usize) -> Distribution2D {
+        let conditional: Vec<Distribution1D> = function.chunks_exact(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(Distribution1D::integral).collect());
+        Distribution2D {
+            conditional,
+            marginal,
+        }
+    }
+    pub fn sample(&self, u: f32, v: f32) -> (f32, f32, f32) {
+        let (y, pdf_y, row) = self.marginal.sample(v);
+        let (x, pdf_x, _) = self.conditional[row].sample(u);
+        (x, y, pdf_x * pdf_y)
+    }
+    pub fn pdf(&self, x: f32, y: f32) -> f32 {
+        let row = ((y * self.marginal.len() as f32) as usize).min(self.marginal.len() - 1);
+        self.marginal.pdf(y) * self.conditional[row].pdf(x)
+    }
+}
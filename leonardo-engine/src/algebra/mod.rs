@@ -1,2 +1,8 @@
+pub mod distribution;
+pub mod matrix;
+pub mod noise;
+pub mod onb;
+pub mod roots;
+pub mod transform;
 pub mod utility;
-pub mod vec3;
\ No newline at end of file
+pub mod vec3;
@@ -0,0 +1,223 @@
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::algebra::utility::mix_seed;
+use crate::algebra::vec3::Point3;
+
+const PERMUTATION_SIZE: usize = 256;
+
+pub struct Perlin {
+    permutation: [u8; PERMUTATION_SIZE * 2],
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut values: Vec<u8> = (0..PERMUTATION_SIZE).map(|i| i as u8).collect();
+        values.shuffle(&mut SmallRng::seed_from_u64(seed));
+        let mut permutation = [0; PERMUTATION_SIZE * 2];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = values[i % PERMUTATION_SIZE];
+        }
+        Perlin {
+            permutation
+        }
+    }
+    fn hash(&self, i: usize) -> usize {
+        self.permutation[i] as usize
+    }
+    pub fn noise(&self, point: Point3) -> f32 {
+        let (fx, fy, fz) = (point.x().floor(), point.y().floor(), point.z().floor());
+        let xi = (fx as i64 & 255) as usize;
+        let yi = (fy as i64 & 255) as usize;
+        let zi = (fz as i64 & 255) as usize;
+        let (x, y, z) = (point.x() - fx, point.y() - fy, point.z() - fz);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = self.hash(xi) + yi;
+        let aa = self.hash(a) + zi;
+        let ab = self.hash(a + 1) + zi;
+        let b = self.hash(xi + 1) + yi;
+        let ba = self.hash(b) + zi;
+        let bb = self.hash(b + 1) + zi;
+        let p = &self.permutation;
+
+        lerp(w,
+            lerp(v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z))),
+            lerp(v,
+                lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))))
+    }
+    pub fn fbm(&self, point: Point3, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut normalization = 0.0;
+        let mut p = point;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p);
+            normalization += amplitude;
+            amplitude *= 0.5;
+            p = p * 2.0;
+        }
+        if normalization > 0.0 { sum / normalization } else { 0.0 }
+    }
+    pub fn turbulence(&self, point: Point3, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut p = point;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p).abs();
+            amplitude *= 0.5;
+            p = p * 2.0;
+        }
+        sum
+    }
+}
+
+pub struct Worley {
+    seed: u64,
+}
+
+fn unit_from_bits(bits: u64) -> f32 {
+    (bits & 0xff_ffff) as f32 / 16_777_216.0
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Worley {
+        Worley {
+            seed
+        }
+    }
+    fn feature_point(&self, cell: [i64; 3]) -> Point3 {
+        let hash = cell.iter().fold(self.seed, |hash, &coordinate| mix_seed(hash, coordinate as u64));
+        Point3::new(
+            cell[0] as f32 + unit_from_bits(hash),
+            cell[1] as f32 + unit_from_bits(hash >> 24),
+            cell[2] as f32 + unit_from_bits(hash >> 40),
+        )
+    }
+    pub fn distances(&self, point: Point3) -> (f32, f32) {
+        let base = [point.x().floor() as i64, point.y().floor() as i64, point.z().floor() as i64];
+        let mut f1 = f32::MAX;
+        let mut f2 = f32::MAX;
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let feature = self.feature_point([base[0] + dx, base[1] + dy, base[2] + dz]);
+                    let distance = (feature - point).length();
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+    pub fn noise(&self, point: Point3) -> f32 {
+        self.distances(point).0
+    }
+    pub fn fbm(&self, point: Point3, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut normalization = 0.0;
+        let mut p = point;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p);
+            normalization += amplitude;
+            amplitude *= 0.5;
+            p = p * 2.0;
+        }
+        if normalization > 0.0 { sum / normalization } else { 0.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::{random_in_range_f32, seed_rng};
+
+    fn random_points(count: usize) -> Vec<Point3> {
+        seed_rng(4);
+        (0..count)
+            .map(|_| Point3::new(random_in_range_f32(-50.0, 50.0), random_in_range_f32(-50.0, 50.0), random_in_range_f32(-50.0, 50.0)))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_noise() {
+        let (perlin, other_perlin) = (Perlin::new(42), Perlin::new(42));
+        let (worley, other_worley) = (Worley::new(42), Worley::new(42));
+        for point in random_points(1000) {
+            assert_eq!(perlin.noise(point), other_perlin.noise(point));
+            assert_eq!(perlin.fbm(point, 5), other_perlin.fbm(point, 5));
+            assert_eq!(worley.distances(point), other_worley.distances(point));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let points = random_points(100);
+        let differs = |a: &dyn Fn(Point3) -> f32, b: &dyn Fn(Point3) -> f32| points.iter().any(|&p| a(p) != b(p));
+        let (perlin, other_perlin) = (Perlin::new(1), Perlin::new(2));
+        assert!(differs(&|p| perlin.noise(p), &|p| other_perlin.noise(p)));
+        let (worley, other_worley) = (Worley::new(1), Worley::new(2));
+        assert!(differs(&|p| worley.noise(p), &|p| other_worley.noise(p)));
+    }
+
+    #[test]
+    fn perlin_noise_is_zero_on_the_lattice() {
+        let perlin = Perlin::new(7);
+        for x in -3..=3 {
+            for y in -3..=3 {
+                for z in -3..=3 {
+                    let point = Point3::new(x as f32 * 17.0, y as f32, z as f32 * 5.0);
+                    assert_eq!(perlin.noise(point), 0.0, "{}", point);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        let perlin = Perlin::new(3);
+        let worley = Worley::new(3);
+        let (mut lowest, mut highest) = (0.0_f32, 0.0_f32);
+        for point in random_points(20_000) {
+            let value = perlin.noise(point);
+            lowest = lowest.min(value);
+            highest = highest.max(value);
+            assert!((-1.0..=1.0).contains(&perlin.fbm(point, 6)), "{}", point);
+            assert!(perlin.turbulence(point, 6) >= 0.0, "{}", point);
+            let (f1, f2) = worley.distances(point);
+            assert!(0.0 <= f1 && f1 <= f2 && f1 <= 3.0_f32.sqrt(), "{} {} at {}", f1, f2, point);
+        }
+        assert!(lowest >= -1.0 && highest <= 1.0, "{} {}", lowest, highest);
+        assert!(lowest < -0.5 && highest > 0.5, "{} {}", lowest, highest);
+    }
+}
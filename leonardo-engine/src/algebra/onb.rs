@@ -0,0 +1,40 @@
+use crate::algebra::vec3::Vec3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Onb {
+        let w = Vec3::unit_vector(n);
+        let sign = 1.0_f32.copysign(w.z());
+        let a = -1.0 / (sign + w.z());
+        let b = w.x() * w.y() * a;
+        Onb {
+            u: Vec3::new(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x()),
+            v: Vec3::new(b, sign + w.y() * w.y() * a, -w.y()),
+            w,
+        }
+    }
+    pub fn from_axes(u: Vec3, v: Vec3, w: Vec3) -> Onb {
+        Onb { u, v, w }
+    }
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+    pub fn w(&self) -> Vec3 {
+        self.w
+    }
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        self.u * a.x() + self.v * a.y() + self.w * a.z()
+    }
+    pub fn coordinates(&self, a: Vec3) -> Vec3 {
+        Vec3::new(self.u.dot(a), self.v.dot(a), self.w.dot(a))
+    }
+}
@@ -0,0 +1,110 @@
+use crate::algebra::matrix::Matrix4;
+use crate::algebra::utility::degrees_to_radians;
+use crate::algebra::vec3::{Point3, Vec3};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Option<Transform> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+    pub fn translate(offset: Vec3) -> Transform {
+        Transform {
+            matrix: Matrix4::translation(offset),
+            inverse: Matrix4::translation(-offset),
+        }
+    }
+    pub fn scale(factors: Vec3) -> Transform {
+        Transform {
+            matrix: Matrix4::scaling(factors),
+            inverse: Matrix4::scaling(Vec3::new(1.0 / factors.x(), 1.0 / factors.y(), 1.0 / factors.z())),
+        }
+    }
+    pub fn rotate(axis: Vec3, degrees: f32) -> Transform {
+        let matrix = Matrix4::rotation(axis, degrees_to_radians(degrees));
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+    pub fn rotate_x(degrees: f32) -> Transform {
+        Transform::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+    pub fn rotate_y(degrees: f32) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+    pub fn rotate_z(degrees: f32) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+    pub fn then(&self, next: Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+    pub fn point(&self, p: Point3) -> Point3 {
+        self.matrix.transform_point(p)
+    }
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).length() < 1e-5, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = Transform::scale(Vec3::new(2.0, 1.0, 0.5)).then(Transform::rotate_y(30.0));
+        let normal = Vec3::new(1.0, 1.0, 1.0);
+        for &tangent in &[Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, -1.0), Vec3::new(1.0, 0.0, -1.0)] {
+            assert!(transform.normal(normal).dot(transform.vector(tangent)).abs() < 1e-5);
+        }
+        assert_close(Transform::scale(Vec3::new(2.0, 1.0, 1.0)).normal(normal), Vec3::new(0.5, 1.0, 1.0));
+    }
+
+    #[test]
+    fn composed_transforms_apply_in_order_and_invert() {
+        let transform = Transform::rotate_z(90.0).then(Transform::translate(Vec3::new(1.0, 0.0, 0.0)));
+        let point = Point3::new(1.0, 0.0, 0.0);
+        assert_close(transform.point(point), Point3::new(1.0, 1.0, 0.0));
+        assert_close(transform.inverse().point(transform.point(point)), point);
+        assert_close(transform.vector(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 1.0, 0.0));
+    }
+}
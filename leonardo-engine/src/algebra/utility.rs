@@ -1,16 +1,32 @@
+use std::cell::RefCell;
 use std::f32::consts::PI;
 
-use rand::Rng;
-use rand::rngs::ThreadRng;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
 
-pub fn random_f32(mut thread_rng: ThreadRng) -> f32 {
-    thread_rng.gen::<f32>()
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(0));
 }
 
-pub fn random_in_range_f32(mut thread_rng: ThreadRng, min: f32, max: f32) -> f32 {
-    thread_rng.gen_range(min, max)
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+pub fn mix_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(0x9e37_79b9_7f4a_7c15).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn random_f32() -> f32 {
+    RNG.with(|rng| rng.borrow_mut().gen::<f32>())
+}
+
+pub fn random_in_range_f32(min: f32, max: f32) -> f32 {
+    RNG.with(|rng| rng.borrow_mut().gen_range(min, max))
 }
 
 pub fn degrees_to_radians(degrees: f32) -> f32 {
     degrees * (PI / 180.0)
-}
\ No newline at end of file
+}
@@ -1,7 +1,9 @@
 use core::fmt;
 use std::f32::consts::PI;
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::algebra::utility::{random_f32, random_in_range_f32};
 
@@ -48,35 +50,42 @@ impl Vec3 {
             ]
         }
     }
+    pub fn min(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            e: [a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())]
+        }
+    }
+    pub fn max(a: Vec3, b: Vec3) -> Vec3 {
+        Vec3 {
+            e: [a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())]
+        }
+    }
     pub fn unit_vector(vec3: Vec3) -> Vec3 {
         vec3 / vec3.length()
     }
     pub fn random_unit_vector() -> Vec3 {
-        let rnd = rand::thread_rng();
-        let a = random_in_range_f32(rnd, 0.0, 2.0 * PI);
-        let z = random_in_range_f32(rnd, -1.0, 1.0);
+        let a = random_in_range_f32(0.0, 2.0 * PI);
+        let z = random_in_range_f32(-1.0, 1.0);
         let r = (1.0 - z * z).sqrt();
         Vec3 {
             e: [r * a.cos(), r * a.sin(), z]
         }
     }
     pub fn random() -> Vec3 {
-        let rnd = rand::thread_rng();
         Vec3 {
             e: [
-                random_f32(rnd),
-                random_f32(rnd),
-                random_f32(rnd)
+                random_f32(),
+                random_f32(),
+                random_f32()
             ]
         }
     }
     pub fn random_in_range(min: f32, max: f32) -> Vec3 {
-        let rnd = rand::thread_rng();
         Vec3 {
             e: [
-                random_in_range_f32(rnd, min, max),
-                random_in_range_f32(rnd, min, max),
-                random_in_range_f32(rnd, min, max)
+                random_in_range_f32(min, max),
+                random_in_range_f32(min, max),
+                random_in_range_f32(min, max)
             ]
         }
     }
@@ -89,12 +98,29 @@ impl Vec3 {
             return p;
         }
     }
+    pub fn random_in_unit_disk() -> Vec3 {
+        loop {
+            let p = Vec3::new(random_in_range_f32(-1.0, 1.0), random_in_range_f32(-1.0, 1.0), 0.0);
+            if p.length_square() >= 1.0 {
+                continue;
+            }
+            return p;
+        }
+    }
     pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
         let rnd_in_unit_sphere = Vec3::random_in_unit_sphere();
         if rnd_in_unit_sphere.dot(normal) > 0.0 {
             return rnd_in_unit_sphere;
         }
-        return -rnd_in_unit_sphere;
+        -rnd_in_unit_sphere
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.e[index]
     }
 }
 
@@ -162,4 +188,18 @@ impl Display for Vec3 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "[{}, {}, {}]", self.x(), self.y(), self.z())
     }
-}
\ No newline at end of file
+}
+
+impl Serialize for Vec3 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let shortest = |x: f32| x.to_string().parse::<f64>().unwrap_or(x as f64);
+        [shortest(self.x()), shortest(self.y()), shortest(self.z())].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vec3 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let e = <[f32; 3]>::deserialize(deserializer)?;
+        Ok(Vec3 { e })
+    }
+}
@@ -0,0 +1,60 @@
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::ray::Ray;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb {
+    minimum: Point3,
+    maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(minimum: Point3, maximum: Point3) -> Aabb {
+        Aabb {
+            minimum,
+            maximum,
+        }
+    }
+    pub fn min(&self) -> Point3 {
+        self.minimum
+    }
+    pub fn max(&self) -> Point3 {
+        self.maximum
+    }
+    pub fn centroid(&self) -> Point3 {
+        (self.minimum + self.maximum) * 0.5
+    }
+    pub fn surface_area(&self) -> f32 {
+        let d = self.maximum - self.minimum;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+    pub fn contains(&self, point: Point3) -> bool {
+        (0..3).all(|axis| point[axis] >= self.minimum[axis] && point[axis] <= self.maximum[axis])
+    }
+    pub fn surrounding(box0: Aabb, box1: Aabb) -> Aabb {
+        Aabb {
+            minimum: Vec3::min(box0.minimum, box1.minimum),
+            maximum: Vec3::max(box0.maximum, box1.maximum),
+        }
+    }
+    pub fn hit(&self, ray: Ray, t_min: f32, t_max: f32) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+    pub fn clip(&self, ray: Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction()[axis];
+            let mut t0 = (self.minimum[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1 = (self.maximum[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
@@ -0,0 +1,90 @@
+use crate::algebra::vec3::{Color, Vec3};
+use crate::graphics::ray::Ray;
+
+pub trait Background: Send + Sync {
+    fn color(&self, ray: Ray) -> Color;
+    fn pdf_value(&self, _direction: Vec3) -> f32 {
+        0.0
+    }
+    fn random(&self) -> Vec3 {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+    fn is_sampled(&self) -> bool {
+        false
+    }
+}
+
+pub struct SkyGradient {
+    bottom: Color,
+    top: Color,
+}
+
+impl SkyGradient {
+    pub fn new(bottom: Color, top: Color) -> SkyGradient {
+        SkyGradient {
+            bottom,
+            top,
+        }
+    }
+}
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        SkyGradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for SkyGradient {
+    fn color(&self, ray: Ray) -> Color {
+        let unit_direction = Vec3::unit_vector(ray.direction());
+        let t = 0.5 * (unit_direction.y() + 1.0);
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
+
+pub struct SolidBackground {
+    color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> SolidBackground {
+        SolidBackground {
+            color
+        }
+    }
+    pub fn black() -> SolidBackground {
+        SolidBackground::new(Color::new(0.0, 0.0, 0.0))
+    }
+}
+
+impl Background for SolidBackground {
+    fn color(&self, _ray: Ray) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn assert_sampling_matches_uniform(background: &dyn Background, excluded_cone: Option<(Vec3, f32, f32)>, tolerance: f32) {
+    use std::f32::consts::PI;
+    use crate::algebra::vec3::Point3;
+    use crate::graphics::tonemap::luminance;
+
+    const SAMPLES: usize = 200_000;
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    let mut sampled = 0.0;
+    let mut uniform = 0.0;
+    for _ in 0..SAMPLES {
+        let direction = background.random();
+        let pdf = background.pdf_value(direction);
+        if pdf > 0.0 {
+            sampled += luminance(background.color(Ray::new(origin, direction))) / pdf;
+        }
+        let direction = Vec3::random_unit_vector();
+        if excluded_cone.is_none_or(|(axis, cos_max, _)| direction.dot(axis) < cos_max) {
+            uniform += luminance(background.color(Ray::new(origin, direction))) * 4.0 * PI;
+        }
+    }
+    let cone = excluded_cone.map_or(0.0, |(_, cos_max, radiance)| radiance * 2.0 * PI * (1.0 - cos_max));
+    let (sampled, uniform) = (sampled / SAMPLES as f32, uniform / SAMPLES as f32 + cone);
+    assert!((sampled - uniform).abs() < tolerance * uniform, "{} {}", sampled, uniform);
+}
@@ -0,0 +1,264 @@
+use crate::algebra::vec3::Point3;
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable, HittableStore};
+use crate::graphics::ray::Ray;
+
+const BUCKETS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f32 = 0.125;
+
+enum BvhKind {
+    Leaf(Vec<Box<dyn Hittable>>),
+    Branch {
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+        axis: usize,
+    },
+}
+
+pub struct BvhNode {
+    bbox: Aabb,
+    kind: BvhKind,
+}
+
+struct Primitive {
+    object: Box<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+impl BvhNode {
+    pub fn new(store: HittableStore, time0: f32, time1: f32) -> BvhNode {
+        let primitives: Vec<Primitive> = store.into_vec()
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box(time0, time1)
+                    .expect("every object stored in a BvhNode must have a bounding box");
+                Primitive {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+        if primitives.is_empty() {
+            let origin = Point3::new(0.0, 0.0, 0.0);
+            return BvhNode {
+                bbox: Aabb::new(origin, origin),
+                kind: BvhKind::Leaf(Vec::new()),
+            };
+        }
+        BvhNode::build(primitives)
+    }
+
+    fn build(mut primitives: Vec<Primitive>) -> BvhNode {
+        let bbox = primitives.iter()
+            .skip(1)
+            .fold(primitives[0].bbox, |acc, p| Aabb::surrounding(acc, p.bbox));
+        if primitives.len() == 1 {
+            return BvhNode::leaf(bbox, primitives);
+        }
+
+        let centroid_bounds = primitives.iter()
+            .skip(1)
+            .fold(Aabb::new(primitives[0].centroid, primitives[0].centroid),
+                  |acc, p| Aabb::surrounding(acc, Aabb::new(p.centroid, p.centroid)));
+
+        let mut best: Option<(usize, usize, f32)> = None;
+        for axis in 0..3 {
+            let min = centroid_bounds.min()[axis];
+            let extent = centroid_bounds.max()[axis] - min;
+            if extent <= 0.0 {
+                continue;
+            }
+            let mut counts = [0usize; BUCKETS];
+            let mut bounds: [Option<Aabb>; BUCKETS] = [None; BUCKETS];
+            for p in primitives.iter() {
+                let b = bucket_index(p.centroid[axis], min, extent);
+                counts[b] += 1;
+                bounds[b] = Some(surround(bounds[b], p.bbox));
+            }
+            for split in 1..BUCKETS {
+                let (left_count, left_box) = accumulate(&counts[..split], &bounds[..split]);
+                let (right_count, right_box) = accumulate(&counts[split..], &bounds[split..]);
+                if left_count == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = left_count as f32 * left_box.unwrap().surface_area()
+                    + right_count as f32 * right_box.unwrap().surface_area();
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+
+        let (axis, split, cost) = match best {
+            Some(best) => best,
+            None => {
+                if primitives.len() <= MAX_LEAF_SIZE {
+                    return BvhNode::leaf(bbox, primitives);
+                }
+                let axis = longest_axis(bbox);
+                let mid = primitives.len() / 2;
+                let right = primitives.split_off(mid);
+                return BvhNode::branch(bbox, primitives, right, axis);
+            }
+        };
+
+        let area = bbox.surface_area();
+        let split_cost = TRAVERSAL_COST + if area > 0.0 { cost / area } else { 0.0 };
+        if primitives.len() <= MAX_LEAF_SIZE && split_cost >= primitives.len() as f32 {
+            return BvhNode::leaf(bbox, primitives);
+        }
+
+        let min = centroid_bounds.min()[axis];
+        let extent = centroid_bounds.max()[axis] - min;
+        let (left, right): (Vec<Primitive>, Vec<Primitive>) = primitives.into_iter()
+            .partition(|p| bucket_index(p.centroid[axis], min, extent) < split);
+        BvhNode::branch(bbox, left, right, axis)
+    }
+
+    fn leaf(bbox: Aabb, primitives: Vec<Primitive>) -> BvhNode {
+        BvhNode {
+            bbox,
+            kind: BvhKind::Leaf(primitives.into_iter().map(|p| p.object).collect()),
+        }
+    }
+
+    fn branch(bbox: Aabb, left: Vec<Primitive>, right: Vec<Primitive>, axis: usize) -> BvhNode {
+        BvhNode {
+            bbox,
+            kind: BvhKind::Branch {
+                left: Box::new(BvhNode::build(left)),
+                right: Box::new(BvhNode::build(right)),
+                axis,
+            },
+        }
+    }
+}
+
+fn bucket_index(centroid: f32, min: f32, extent: f32) -> usize {
+    let b = (BUCKETS as f32 * (centroid - min) / extent) as usize;
+    if b >= BUCKETS { BUCKETS - 1 } else { b }
+}
+
+fn surround(current: Option<Aabb>, other: Aabb) -> Aabb {
+    match current {
+        Some(current) => Aabb::surrounding(current, other),
+        None => other
+    }
+}
+
+fn accumulate(counts: &[usize], bounds: &[Option<Aabb>]) -> (usize, Option<Aabb>) {
+    let mut count = 0;
+    let mut bbox: Option<Aabb> = None;
+    for (c, b) in counts.iter().zip(bounds.iter()) {
+        count += c;
+        if let Some(b) = b {
+            bbox = Some(surround(bbox, *b));
+        }
+    }
+    (count, bbox)
+}
+
+fn longest_axis(bbox: Aabb) -> usize {
+    let d = bbox.max() - bbox.min();
+    if d.x() > d.y() && d.x() > d.z() {
+        0
+    } else if d.y() > d.z() {
+        1
+    } else {
+        2
+    }
+}
+
+impl BvhNode {
+    fn closest<F>(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord, hit: &F) -> Option<HitRecord>
+    where
+        F: Fn(&dyn Hittable, Ray, f32, f32, &HitRecord) -> Option<HitRecord>,
+    {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+        match &self.kind {
+            BvhKind::Leaf(objects) => {
+                let mut closest: Option<HitRecord> = None;
+                let mut closest_so_far = t_max;
+                for object in objects.iter() {
+                    if let Some(new_hit_record) = hit(object.as_ref(), ray, t_min, closest_so_far, hit_record) {
+                        closest_so_far = new_hit_record.t;
+                        closest = Some(new_hit_record);
+                    }
+                }
+                closest
+            }
+            BvhKind::Branch { left, right, axis } => {
+                let (first, second) = if ray.direction()[*axis] < 0.0 {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                let first_hit = first.closest(ray, t_min, t_max, hit_record, hit);
+                let closest_so_far = first_hit.as_ref().map_or(t_max, |rec| rec.t);
+                match second.closest(ray, t_min, closest_so_far, hit_record, hit) {
+                    Some(second_hit) => Some(second_hit),
+                    None => first_hit
+                }
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.closest(ray, t_min, t_max, hit_record, &|object, ray, t_min, t_max, rec| object.hit(ray, t_min, t_max, rec))
+    }
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.closest(ray, t_min, t_max, hit_record, &|object, ray, t_min, t_max, rec| object.hit_surface(ray, t_min, t_max, rec))
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return 1.0;
+        }
+        match &self.kind {
+            BvhKind::Leaf(objects) => objects.iter().map(|object| object.transmittance(ray, t_min, t_max)).product(),
+            BvhKind::Branch { left, right, .. } => left.transmittance(ray, t_min, t_max) * right.transmittance(ray, t_min, t_max)
+        }
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::algebra::utility::{random_in_range_f32, seed_rng};
+    use crate::algebra::vec3::Vec3;
+    use crate::graphics::sphere::Sphere;
+
+    #[test]
+    fn matches_linear_search() {
+        seed_rng(3);
+        let spheres: Vec<Arc<Sphere>> = (0..300)
+            .map(|_| Arc::new(Sphere::new(Vec3::random_in_range(-10.0, 10.0), random_in_range_f32(0.05, 1.0), None)))
+            .collect();
+        let mut linear = HittableStore::new();
+        let mut store = HittableStore::new();
+        for sphere in &spheres {
+            linear.store(Box::new(sphere.clone()));
+            store.store(Box::new(sphere.clone()));
+        }
+        let bvh = BvhNode::new(store, 0.0, 0.0);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let ray = Ray::new(Vec3::random_in_range(-12.0, 12.0), Vec3::random_unit_vector());
+            let expected = linear.hit(ray, 0.001, f32::MAX, &HitRecord::new_def()).map(|record| record.t);
+            let actual = bvh.hit(ray, 0.001, f32::MAX, &HitRecord::new_def()).map(|record| record.t);
+            assert_eq!(actual, expected, "{} {}", ray.origin(), ray.direction());
+            hits += expected.is_some() as usize;
+        }
+        assert!(hits > 100, "{}", hits);
+    }
+}
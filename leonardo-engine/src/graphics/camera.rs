@@ -1,9 +1,31 @@
-use crate::algebra::utility::degrees_to_radians;
+use crate::algebra::utility::{degrees_to_radians, random_in_range_f32};
 use crate::algebra::vec3::{Point3, Vec3};
 use crate::graphics::ray::Ray;
 
+const MIN_UP_SINE: f32 = 1e-4;
+
+pub fn has_valid_up(view: Vec3, vup: Vec3) -> bool {
+    Vec3::unit_vector(vup).cross(Vec3::unit_vector(view)).length() >= MIN_UP_SINE
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Focus {
+    LookAt,
+    Distance(f32),
+}
+
 pub struct Camera {
     origin: Point3,
+    look_at: Point3,
+    viewport_width: f32,
+    viewport_height: f32,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    lens_radius: f32,
+    focus: Focus,
+    shutter_open: f32,
+    shutter_close: f32,
     lower_left_corner: Point3,
     horizontal: Vec3,
     vertical: Vec3,
@@ -16,21 +38,187 @@ impl Camera {
         let viewport_height = 2.0_f32 * h;
         let viewport_width = aspect_ratio * viewport_height;
 
+        debug_assert!(has_valid_up(look_from - look_at, vup), "`vup` must not be parallel to the view direction");
         let w = Vec3::unit_vector(look_from - look_at);
         let u = Vec3::unit_vector(vup.cross(w));
         let v = w.cross(u);
 
-        let horizontal = Vec3::new(viewport_width, 0.0, 0.0);
-        let vertical = Vec3::new(0.0, viewport_height, 0.0);
-        let lower_left_corner = look_from - (horizontal / 2.0) - (vertical / 2.0) - w;
-        Camera {
+        let mut camera = Camera {
             origin: look_from,
-            lower_left_corner,
+            look_at,
+            viewport_width,
+            viewport_height,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            focus: Focus::Distance(1.0),
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            lower_left_corner: look_from,
             horizontal: u * viewport_width,
             vertical: v * viewport_height,
+        };
+        camera.update_viewport();
+        camera
+    }
+    pub fn with_aperture(mut self, aperture: f32) -> Camera {
+        self.lens_radius = aperture.max(0.0) / 2.0;
+        self
+    }
+    pub fn with_focus(mut self, focus: Focus) -> Camera {
+        self.focus = focus;
+        self.update_viewport();
+        self
+    }
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Camera {
+        self.shutter_open = open;
+        self.shutter_close = close.max(open);
+        self
+    }
+    pub fn shutter(&self) -> (f32, f32) {
+        (self.shutter_open, self.shutter_close)
+    }
+    pub fn focus_distance(&self) -> f32 {
+        match self.focus {
+            Focus::LookAt => (self.look_at - self.origin).length(),
+            Focus::Distance(distance) => distance,
         }
     }
-    pub fn get_ray(&self, u: f32, v: f32) -> Ray {
-        Ray::new(self.origin, self.lower_left_corner + (self.horizontal * u) + (self.vertical * v) - self.origin)
+    fn update_viewport(&mut self) {
+        let focus_distance = self.focus_distance();
+        self.horizontal = self.u * (self.viewport_width * focus_distance);
+        self.vertical = self.v * (self.viewport_height * focus_distance);
+        self.lower_left_corner = self.origin - (self.horizontal / 2.0) - (self.vertical / 2.0) - self.w * focus_distance;
     }
-}
\ No newline at end of file
+    pub fn get_ray(&self, s: f32, t: f32) -> Ray {
+        let offset = if self.lens_radius > 0.0 {
+            let rd = Vec3::random_in_unit_disk() * self.lens_radius;
+            self.u * rd.x() + self.v * rd.y()
+        } else {
+            Vec3::new(0.0, 0.0, 0.0)
+        };
+        let time = if self.shutter_close > self.shutter_open {
+            random_in_range_f32(self.shutter_open, self.shutter_close)
+        } else {
+            self.shutter_open
+        };
+        let origin = self.origin + offset;
+        Ray::with_time(origin, self.lower_left_corner + (self.horizontal * s) + (self.vertical * t) - origin, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn orientations() -> Vec<(Point3, Point3, Vec3)> {
+        vec![
+            (Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0)),
+            (Point3::new(13.0, 2.0, 3.0), Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            (Point3::new(278.0, 278.0, -800.0), Point3::new(278.0, 278.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            (Point3::new(-2.0, 5.0, 1.0), Point3::new(1.0, -1.0, 4.0), Vec3::new(0.3, 1.0, -0.2)),
+            (Point3::new(1.0, 1.0, 1.0), Point3::new(1.0, 1.0, -4.0), Vec3::new(1.0, 0.0, 0.0)),
+        ]
+    }
+
+    fn assert_parallel(a: Vec3, b: Vec3) {
+        let cosine = Vec3::unit_vector(a).dot(Vec3::unit_vector(b));
+        assert!((cosine - 1.0).abs() < EPSILON, "{} is not parallel to {}", a, b);
+    }
+
+    fn angle_between(a: Vec3, b: Vec3) -> f32 {
+        Vec3::unit_vector(a).dot(Vec3::unit_vector(b)).clamp(-1.0, 1.0).acos()
+    }
+
+    #[test]
+    fn center_ray_points_at_look_at() {
+        for (look_from, look_at, vup) in orientations() {
+            let camera = Camera::new(look_from, look_at, vup, 40.0, 1.5);
+            let ray = camera.get_ray(0.5, 0.5);
+            assert!((ray.origin() - look_from).length() < EPSILON);
+            assert_parallel(ray.direction(), look_at - look_from);
+        }
+    }
+
+    #[test]
+    fn edge_rays_span_the_field_of_view() {
+        let vfov = 50.0;
+        let aspect_ratio = 2.0;
+        let half_height = degrees_to_radians(vfov) / 2.0;
+        let half_width = (aspect_ratio * half_height.tan()).atan();
+        for (look_from, look_at, vup) in orientations() {
+            let camera = Camera::new(look_from, look_at, vup, vfov, aspect_ratio);
+            let forward = look_at - look_from;
+            let top = camera.get_ray(0.5, 1.0).direction();
+            let bottom = camera.get_ray(0.5, 0.0).direction();
+            let left = camera.get_ray(0.0, 0.5).direction();
+            let right = camera.get_ray(1.0, 0.5).direction();
+            assert!((angle_between(forward, top) - half_height).abs() < EPSILON);
+            assert!((angle_between(forward, bottom) - half_height).abs() < EPSILON);
+            assert!((angle_between(forward, left) - half_width).abs() < EPSILON);
+            assert!((angle_between(forward, right) - half_width).abs() < EPSILON);
+            assert!(top.dot(vup) > bottom.dot(vup));
+            assert!(Vec3::unit_vector(right).dot(vup.cross(-forward)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn corner_rays_are_symmetric() {
+        for (look_from, look_at, vup) in orientations() {
+            let camera = Camera::new(look_from, look_at, vup, 30.0, 16.0 / 9.0);
+            let forward = look_at - look_from;
+            let lower_left = camera.get_ray(0.0, 0.0).direction();
+            let upper_right = camera.get_ray(1.0, 1.0).direction();
+            let lower_right = camera.get_ray(1.0, 0.0).direction();
+            let upper_left = camera.get_ray(0.0, 1.0).direction();
+            let angle = angle_between(forward, lower_left);
+            for corner in [upper_right, lower_right, upper_left] {
+                assert!((angle_between(forward, corner) - angle).abs() < EPSILON);
+            }
+            assert_parallel(lower_left + upper_right, forward);
+            assert_parallel(lower_right + upper_left, forward);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "`vup` must not be parallel")]
+    fn vup_parallel_to_the_view_direction_is_rejected() {
+        Camera::new(Point3::new(0.0, 5.0, 0.0), Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 40.0, 1.0);
+    }
+
+    #[test]
+    fn focus_distance_follows_look_at() {
+        let camera = Camera::new(Point3::new(3.0, 4.0, 0.0), Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 40.0, 1.0)
+            .with_focus(Focus::LookAt);
+        assert!((camera.focus_distance() - 5.0).abs() < EPSILON);
+        let camera = camera.with_focus(Focus::Distance(2.5));
+        assert!((camera.focus_distance() - 2.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn lens_rays_converge_on_the_focus_plane() {
+        seed_rng(7);
+        for (look_from, look_at, vup) in orientations() {
+            let pinhole = Camera::new(look_from, look_at, vup, 40.0, 1.5).with_focus(Focus::LookAt);
+            let lens = Camera::new(look_from, look_at, vup, 40.0, 1.5)
+                .with_aperture(0.5)
+                .with_focus(Focus::LookAt);
+            let scale = pinhole.focus_distance();
+            for &(s, t) in &[(0.5, 0.5), (0.0, 0.0), (1.0, 1.0), (0.25, 0.8)] {
+                let target = pinhole.get_ray(s, t).at(1.0);
+                let mut origins_differ = false;
+                for _ in 0..16 {
+                    let ray = lens.get_ray(s, t);
+                    assert!((ray.at(1.0) - target).length() < EPSILON * scale);
+                    assert!((ray.origin() - look_from).length() <= 0.25 + EPSILON);
+                    origins_differ |= (ray.origin() - look_from).length() > EPSILON;
+                }
+                assert!(origins_differ);
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::algebra::onb::Onb;
+use crate::algebra::roots::solve_quadratic;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::cylinder::{angle_uv, disk_extent};
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+pub struct Cone {
+    base: Point3,
+    apex: Point3,
+    axis: Onb,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Cone {
+    pub fn new(base: Point3, apex: Point3, radius: f32, material: Option<Arc<dyn Material>>) -> Cone {
+        Cone {
+            base,
+            apex,
+            axis: Onb::new(apex - base),
+            radius: radius.abs(),
+            height: (apex - base).length(),
+            capped: true,
+            material,
+        }
+    }
+    pub fn with_cap(mut self, capped: bool) -> Cone {
+        self.capped = capped;
+        self
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let o = self.axis.coordinates(ray.origin() - self.base);
+        let d = self.axis.coordinates(ray.direction());
+        let k_squared = (self.radius / self.height) * (self.radius / self.height);
+        let mut closest: Option<(f32, Vec3, f32, f32)> = None;
+        let mut closest_so_far = t_max;
+
+        let h = self.height - o.z();
+        let a = d.x() * d.x() + d.y() * d.y() - k_squared * d.z() * d.z();
+        let half_b = o.x() * d.x() + o.y() * d.y() + k_squared * h * d.z();
+        let c = o.x() * o.x() + o.y() * o.y() - k_squared * h * h;
+        for t in solve_quadratic(a as f64, 2.0 * half_b as f64, c as f64) {
+            let t = t as f32;
+            let z = o.z() + t * d.z();
+            if t > t_min && t < closest_so_far && (0.0..=self.height).contains(&z) {
+                let (x, y) = (o.x() + t * d.x(), o.y() + t * d.y());
+                let normal = Vec3::unit_vector(Vec3::new(x, y, k_squared * (self.height - z)));
+                closest = Some((t, normal, angle_uv(x, y), z / self.height));
+                closest_so_far = t;
+                break;
+            }
+        }
+        if self.capped && d.z() != 0.0 {
+            let t = -o.z() / d.z();
+            if t > t_min && t < closest_so_far {
+                let (x, y) = (o.x() + t * d.x(), o.y() + t * d.y());
+                let r_squared = x * x + y * y;
+                if r_squared <= self.radius * self.radius {
+                    closest = Some((t, Vec3::new(0.0, 0.0, -1.0), angle_uv(x, y), r_squared.sqrt() / self.radius));
+                }
+            }
+        }
+
+        let (t, normal, u, v) = closest?;
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = ray.at(t);
+        hit_record.u = u;
+        hit_record.v = v;
+        if normal.z() >= 0.0 {
+            let p = o + d * t;
+            let ring = (p.x() * p.x() + p.y() * p.y()).sqrt();
+            let slope = if ring > 0.0 { self.radius / ring } else { 0.0 };
+            hit_record.dpdu = self.axis.local(Vec3::new(-p.y(), p.x(), 0.0) * (2.0 * PI));
+            hit_record.dpdv = self.axis.local(Vec3::new(-p.x() * slope, -p.y() * slope, self.height));
+        }
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.axis.local(normal)))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let extent = disk_extent(self.axis.w(), self.radius);
+        Some(Aabb::new(
+            Vec3::min(self.base - extent, self.apex),
+            Vec3::max(self.base + extent, self.apex),
+        ))
+    }
+}
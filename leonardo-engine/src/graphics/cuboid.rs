@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::quad::Quad;
+use crate::graphics::ray::Ray;
+
+pub struct Cuboid {
+    minimum: Point3,
+    maximum: Point3,
+    sides: Vec<Quad>,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Option<Arc<dyn Material>>) -> Cuboid {
+        let min = Vec3::min(a, b);
+        let max = Vec3::max(a, b);
+        let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+        let sides = vec![
+            Quad::new(Point3::new(min.x(), min.y(), max.z()), dx, dy, material.clone()),
+            Quad::new(Point3::new(max.x(), min.y(), max.z()), -dz, dy, material.clone()),
+            Quad::new(Point3::new(max.x(), min.y(), min.z()), -dx, dy, material.clone()),
+            Quad::new(Point3::new(min.x(), min.y(), min.z()), dz, dy, material.clone()),
+            Quad::new(Point3::new(min.x(), max.y(), max.z()), dx, -dz, material.clone()),
+            Quad::new(Point3::new(min.x(), min.y(), min.z()), dx, dz, material),
+        ];
+        Cuboid {
+            minimum: min,
+            maximum: max,
+            sides,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        let mut closest: Option<HitRecord> = None;
+        let mut closest_so_far = t_max;
+        for side in self.sides.iter() {
+            if let Some(new_hit_record) = side.hit(ray, t_min, closest_so_far, hit_record) {
+                closest_so_far = new_hit_record.t;
+                closest = Some(new_hit_record);
+            }
+        }
+        closest
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::new(self.minimum, self.maximum))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let sum: f32 = self.sides.iter().map(|side| side.pdf_value(origin, direction)).sum();
+        sum / self.sides.len() as f32
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        let index = ((random_f32() * self.sides.len() as f32) as usize).min(self.sides.len() - 1);
+        self.sides[index].random(origin)
+    }
+}
@@ -0,0 +1,108 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::algebra::onb::Onb;
+use crate::algebra::roots::solve_quadratic;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+pub struct Cylinder {
+    base: Point3,
+    top: Point3,
+    axis: Onb,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: f32, material: Option<Arc<dyn Material>>) -> Cylinder {
+        Cylinder {
+            base,
+            top,
+            axis: Onb::new(top - base),
+            radius: radius.abs(),
+            height: (top - base).length(),
+            capped: true,
+            material,
+        }
+    }
+    pub fn with_caps(mut self, capped: bool) -> Cylinder {
+        self.capped = capped;
+        self
+    }
+}
+
+pub(crate) fn angle_uv(x: f32, y: f32) -> f32 {
+    (y.atan2(x) + PI) / (2.0 * PI)
+}
+
+pub(crate) fn disk_extent(axis: Vec3, radius: f32) -> Vec3 {
+    Vec3::new(
+        radius * (1.0 - axis.x() * axis.x()).max(0.0).sqrt(),
+        radius * (1.0 - axis.y() * axis.y()).max(0.0).sqrt(),
+        radius * (1.0 - axis.z() * axis.z()).max(0.0).sqrt(),
+    )
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let o = self.axis.coordinates(ray.origin() - self.base);
+        let d = self.axis.coordinates(ray.direction());
+        let mut closest: Option<(f32, Vec3, f32, f32)> = None;
+        let mut closest_so_far = t_max;
+
+        let a = d.x() * d.x() + d.y() * d.y();
+        let half_b = o.x() * d.x() + o.y() * d.y();
+        let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+        for t in solve_quadratic(a as f64, 2.0 * half_b as f64, c as f64) {
+            let t = t as f32;
+            let z = o.z() + t * d.z();
+            if t > t_min && t < closest_so_far && (0.0..=self.height).contains(&z) {
+                let (x, y) = (o.x() + t * d.x(), o.y() + t * d.y());
+                closest = Some((t, Vec3::new(x, y, 0.0) / self.radius, angle_uv(x, y), z / self.height));
+                closest_so_far = t;
+                break;
+            }
+        }
+        if self.capped && d.z() != 0.0 {
+            for (z, normal) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (z - o.z()) / d.z();
+                if t <= t_min || t >= closest_so_far {
+                    continue;
+                }
+                let (x, y) = (o.x() + t * d.x(), o.y() + t * d.y());
+                let r_squared = x * x + y * y;
+                if r_squared <= self.radius * self.radius {
+                    closest = Some((t, Vec3::new(0.0, 0.0, normal), angle_uv(x, y), r_squared.sqrt() / self.radius));
+                    closest_so_far = t;
+                }
+            }
+        }
+
+        let (t, normal, u, v) = closest?;
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = ray.at(t);
+        hit_record.u = u;
+        hit_record.v = v;
+        if normal.z() == 0.0 {
+            let p = o + d * t;
+            hit_record.dpdu = self.axis.local(Vec3::new(-p.y(), p.x(), 0.0) * (2.0 * PI));
+            hit_record.dpdv = self.axis.local(Vec3::new(0.0, 0.0, self.height));
+        }
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.axis.local(normal)))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let extent = disk_extent(self.axis.w(), self.radius);
+        Some(Aabb::new(
+            Vec3::min(self.base, self.top) - extent,
+            Vec3::max(self.base, self.top) + extent,
+        ))
+    }
+}
@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::algebra::onb::Onb;
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+const EPSILON: f32 = 1e-8;
+const BOX_PADDING: f32 = 1e-4;
+
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f32,
+    basis: Onb,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f32, material: Option<Arc<dyn Material>>) -> Disk {
+        let normal = Vec3::unit_vector(normal);
+        Disk {
+            center,
+            normal,
+            radius: radius.abs(),
+            basis: Onb::new(normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+        let t = (self.center - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let offset = point - self.center;
+        let distance_squared = offset.length_square();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+        let phi = offset.dot(self.basis.v()).atan2(offset.dot(self.basis.u()));
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = point;
+        hit_record.u = (phi + PI) / (2.0 * PI);
+        hit_record.v = distance_squared.sqrt() / self.radius;
+        if distance_squared > 0.0 {
+            let (x, y) = (offset.dot(self.basis.u()), offset.dot(self.basis.v()));
+            hit_record.dpdu = (self.basis.v() * x - self.basis.u() * y) * (2.0 * PI);
+            hit_record.dpdv = offset * (self.radius / distance_squared.sqrt());
+        }
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.normal))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let n = self.normal;
+        let extent = Vec3::new(
+            self.radius * (1.0 - n.x() * n.x()).max(0.0).sqrt() + BOX_PADDING,
+            self.radius * (1.0 - n.y() * n.y()).max(0.0).sqrt() + BOX_PADDING,
+            self.radius * (1.0 - n.z() * n.z()).max(0.0).sqrt() + BOX_PADDING,
+        );
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let hit_record = match self.hit(Ray::new(origin, direction), 0.001, f32::MAX, &HitRecord::new_def()) {
+            Some(hit_record) => hit_record,
+            None => return 0.0
+        };
+        let distance_squared = hit_record.t * hit_record.t * direction.length_square();
+        let cosine = self.normal.dot(direction).abs() / direction.length();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * PI * self.radius * self.radius)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        let r = self.radius * random_f32().sqrt();
+        let phi = 2.0 * PI * random_f32();
+        let point = self.center + self.basis.local(Vec3::new(r * phi.cos(), r * phi.sin(), 0.0));
+        point - origin
+    }
+}
@@ -0,0 +1,173 @@
+use std::f32::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::algebra::distribution::Distribution2D;
+use crate::algebra::utility::{degrees_to_radians, random_f32};
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::background::Background;
+use crate::graphics::ray::Ray;
+use crate::graphics::sphere::sphere_uv;
+use crate::graphics::texture::{ImageTexture, Texture};
+use crate::graphics::tonemap::luminance;
+
+enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    fn project(direction: Vec3) -> (usize, f32, f32) {
+        let (x, y, z) = (direction.x(), direction.y(), direction.z());
+        let (face, s, t, major) = if x.abs() >= y.abs() && x.abs() >= z.abs() {
+            if x > 0.0 { (CubeFace::PositiveX, -z, -y, x) } else { (CubeFace::NegativeX, z, -y, -x) }
+        } else if y.abs() >= z.abs() {
+            if y > 0.0 { (CubeFace::PositiveY, x, z, y) } else { (CubeFace::NegativeY, x, -z, -y) }
+        } else if z > 0.0 {
+            (CubeFace::PositiveZ, x, -y, z)
+        } else {
+            (CubeFace::NegativeZ, -x, -y, -z)
+        };
+        (face as usize, 0.5 * (s / major + 1.0), 0.5 * (t / major + 1.0))
+    }
+}
+
+pub(crate) fn equirect_uv(direction: Vec3) -> (f32, f32) {
+    let (u, v) = sphere_uv(Vec3::unit_vector(direction));
+    (u, 1.0 - v)
+}
+
+pub(crate) fn equirect_pdf(distribution: &Distribution2D, direction: Vec3) -> f32 {
+    let (u, v) = equirect_uv(direction);
+    let sin_theta = (PI * v).sin();
+    if sin_theta <= 0.0 {
+        return 0.0;
+    }
+    distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+}
+
+pub(crate) fn equirect_direction(u: f32, v: f32) -> Vec3 {
+    let theta = PI * v;
+    let phi = 2.0 * PI * u;
+    Vec3::new(-theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+}
+
+pub struct EnvironmentMap {
+    texture: ImageTexture,
+    distribution: Distribution2D,
+    rotation: f32,
+    intensity: f32,
+}
+
+impl EnvironmentMap {
+    pub fn new(texture: ImageTexture) -> EnvironmentMap {
+        let (width, height) = (texture.width(), texture.height());
+        let texels = texture.texels();
+        let brightest = |x: usize, y: usize| {
+            let rows = y.saturating_sub(1)..(y + 2).min(height);
+            rows.flat_map(|row| (0..3).map(move |dx| row * width + (x + width + dx - 1) % width))
+                .map(|i| luminance(texels[i]))
+                .fold(0.0_f32, f32::max)
+        };
+        let weights: Vec<f32> = (0..width * height)
+            .map(|i| brightest(i % width, i / width) * (PI * ((i / width) as f32 + 0.5) / height as f32).sin())
+            .collect();
+        EnvironmentMap {
+            distribution: Distribution2D::new(&weights, width, height),
+            texture,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<EnvironmentMap> {
+        Ok(EnvironmentMap::new(ImageTexture::load(path)?))
+    }
+    pub fn cube(faces: Vec<ImageTexture>) -> io::Result<EnvironmentMap> {
+        let size = faces.first().map_or(0, ImageTexture::width);
+        if faces.len() != 6 || size == 0 || faces.iter().any(|face| face.width() != size || face.height() != size) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "a cube map needs six square faces of the same size"));
+        }
+        let (width, height) = (4 * size, 2 * size);
+        let texels = (0..width * height)
+            .map(|i| {
+                let u = ((i % width) as f32 + 0.5) / width as f32;
+                let v = ((i / width) as f32 + 0.5) / height as f32;
+                let (face, s, t) = CubeFace::project(equirect_direction(u, v));
+                faces[face].value(s, 1.0 - t, Point3::new(0.0, 0.0, 0.0))
+            })
+            .collect();
+        Ok(EnvironmentMap::new(ImageTexture::new(width, height, texels)))
+    }
+    pub fn load_cube<P: AsRef<Path>>(paths: &[P]) -> io::Result<EnvironmentMap> {
+        EnvironmentMap::cube(paths.iter().map(ImageTexture::load).collect::<io::Result<_>>()?)
+    }
+    pub fn with_rotation(mut self, degrees: f32) -> EnvironmentMap {
+        self.rotation = degrees_to_radians(degrees);
+        self
+    }
+    pub fn with_intensity(mut self, intensity: f32) -> EnvironmentMap {
+        self.intensity = intensity;
+        self
+    }
+    fn rotate(&self, direction: Vec3, angle: f32) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(cos * direction.x() + sin * direction.z(), direction.y(), cos * direction.z() - sin * direction.x())
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn color(&self, ray: Ray) -> Color {
+        let (u, v) = equirect_uv(self.rotate(ray.direction(), -self.rotation));
+        self.texture.value(u, 1.0 - v, ray.origin()) * self.intensity
+    }
+    fn pdf_value(&self, direction: Vec3) -> f32 {
+        equirect_pdf(&self.distribution, self.rotate(direction, -self.rotation))
+    }
+    fn random(&self) -> Vec3 {
+        let (u, v, _) = self.distribution.sample(random_f32(), random_f32());
+        self.rotate(equirect_direction(u, v), self.rotation)
+    }
+    fn is_sampled(&self) -> bool {
+        self.intensity > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+    use crate::graphics::background::assert_sampling_matches_uniform;
+
+    const SAMPLES: usize = 200_000;
+
+    fn sun_map() -> EnvironmentMap {
+        let (width, height) = (64, 32);
+        let texels = (0..width * height)
+            .map(|i| if i % width == 40 && i / width == 9 { Color::new(50.0, 45.0, 40.0) } else { Color::new(0.2, 0.3, 0.5) })
+            .collect();
+        EnvironmentMap::new(ImageTexture::new(width, height, texels)).with_rotation(30.0)
+    }
+
+    #[test]
+    fn importance_sampling_matches_uniform_estimate() {
+        seed_rng(1);
+        assert_sampling_matches_uniform(&sun_map(), None, 0.05);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        seed_rng(2);
+        let map = sun_map();
+        let estimate: f32 = (0..SAMPLES)
+            .map(|_| {
+                let direction = Vec3::random_unit_vector();
+                map.pdf_value(direction) * 4.0 * PI
+            })
+            .sum::<f32>() / SAMPLES as f32;
+        assert!((estimate - 1.0).abs() < 0.05, "{}", estimate);
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+
+use exr::prelude::{f16, read_first_rgba_layer_from_file, Image, SpecificChannels, Vec2, WritableImage};
+
+use crate::algebra::vec3::Color;
+use crate::graphics::image::{Framebuffer, ImageEncoder};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExrPrecision {
+    Half,
+    Float,
+}
+
+pub struct ExrEncoder {
+    precision: ExrPrecision,
+}
+
+impl ExrEncoder {
+    pub fn new(precision: ExrPrecision) -> ExrEncoder {
+        ExrEncoder {
+            precision
+        }
+    }
+}
+
+fn to_io_error(err: exr::error::Error) -> io::Error {
+    match err {
+        exr::error::Error::Io(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+pub fn decode_exr(path: &Path) -> io::Result<(usize, usize, Vec<Color>)> {
+    let image = read_first_rgba_layer_from_file(
+        path,
+        |resolution, _| (resolution.width(), vec![Color::new(0.0, 0.0, 0.0); resolution.area()]),
+        |(width, pixels): &mut (usize, Vec<Color>), position, (r, g, b, _): (f32, f32, f32, f32)| {
+            pixels[position.y() * *width + position.x()] = Color::new(r, g, b);
+        },
+    ).map_err(to_io_error)?;
+    let (width, pixels) = image.layer_data.channel_data.pixels;
+    Ok((width, pixels.len() / width.max(1), pixels))
+}
+
+impl ImageEncoder for ExrEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()> {
+        let size = (framebuffer.width(), framebuffer.height());
+        let mut buffer = Cursor::new(Vec::new());
+        match self.precision {
+            ExrPrecision::Half => {
+                let channels = SpecificChannels::rgb(|Vec2(x, y)| {
+                    let color = framebuffer.get(x, y);
+                    (f16::from_f32(color.x()), f16::from_f32(color.y()), f16::from_f32(color.z()))
+                });
+                Image::from_channels(size, channels).write().to_buffered(&mut buffer).map_err(to_io_error)?;
+            }
+            ExrPrecision::Float => {
+                let channels = SpecificChannels::rgb(|Vec2(x, y)| {
+                    let color = framebuffer.get(x, y);
+                    (color.x(), color.y(), color.z())
+                });
+                Image::from_channels(size, channels).write().to_buffered(&mut buffer).map_err(to_io_error)?;
+            }
+        }
+        writer.write_all(buffer.get_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn round_trip(precision: ExrPrecision) -> (Framebuffer, usize, usize, Vec<Color>) {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        for (index, &value) in [0.0, 0.1, 1.0, 7.5, 1000.0, -0.5].iter().enumerate() {
+            framebuffer.set(index % 3, index / 3, Color::new(value, value * 0.5, index as f32));
+        }
+        let mut bytes = Vec::new();
+        ExrEncoder::new(precision).encode(&framebuffer, &mut bytes).unwrap();
+        let path = env::temp_dir().join(format!("leonardo-{:?}-{}.exr", precision, std::process::id()));
+        fs::write(&path, &bytes).unwrap();
+        let decoded = decode_exr(&path);
+        fs::remove_file(&path).unwrap();
+        let (width, height, pixels) = decoded.unwrap();
+        (framebuffer, width, height, pixels)
+    }
+
+    #[test]
+    fn float_exr_round_trips_exactly() {
+        let (framebuffer, width, height, pixels) = round_trip(ExrPrecision::Float);
+        assert_eq!((width, height), (3, 2));
+        for (original, decoded) in framebuffer.pixels().iter().zip(&pixels) {
+            assert_eq!((decoded.x(), decoded.y(), decoded.z()), (original.x(), original.y(), original.z()));
+        }
+    }
+
+    #[test]
+    fn half_exr_round_trips_within_half_precision() {
+        let (framebuffer, width, height, pixels) = round_trip(ExrPrecision::Half);
+        assert_eq!((width, height), (3, 2));
+        for (original, decoded) in framebuffer.pixels().iter().zip(&pixels) {
+            for &(original, decoded) in &[(original.x(), decoded.x()), (original.y(), decoded.y()), (original.z(), decoded.z())] {
+                assert!((decoded - original).abs() <= original.abs() / 1024.0, "{} decoded as {}", original, decoded);
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::{self, Write};
+
+use crate::algebra::vec3::Color;
+use crate::graphics::image::{Framebuffer, ImageEncoder};
+
+pub struct HdrEncoder;
+
+const MIN_RLE_WIDTH: usize = 8;
+const MAX_RLE_WIDTH: usize = 0x7fff;
+const MAX_EXPONENT: i32 = 127;
+
+pub fn to_rgbe(color: Color) -> [u8; 4] {
+    let max = color.x().max(color.y()).max(color.z());
+    if max.is_nan() || max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // frexp on the bits, since log2 rounds up just below powers of two; colors past 2^127 saturate
+    let exponent = (((max.to_bits() >> 23) & 0xff) as i32 - 126).min(MAX_EXPONENT);
+    let scale = 2.0_f32.powi(8 - exponent);
+    let channel = |x: f32| (x.max(0.0) * scale).min(255.0) as u8;
+    [channel(color.x()), channel(color.y()), channel(color.z()), (exponent + 128) as u8]
+}
+
+pub fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let scale = 2.0_f32.powi(rgbe[3] as i32 - 136);
+    Color::new((rgbe[0] as f32 + 0.5) * scale, (rgbe[1] as f32 + 0.5) * scale, (rgbe[2] as f32 + 0.5) * scale)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_scanline(data: &[u8], offset: &mut usize, width: usize) -> io::Result<Vec<[u8; 4]>> {
+    let header = data.get(*offset..*offset + 4).ok_or_else(|| invalid_data("truncated scanline"))?;
+    let run_length_encoded = (MIN_RLE_WIDTH..=MAX_RLE_WIDTH).contains(&width)
+        && header[0] == 2 && header[1] == 2 && header[2] & 0x80 == 0;
+    if !run_length_encoded {
+        let bytes = data.get(*offset..*offset + 4 * width).ok_or_else(|| invalid_data("truncated scanline"))?;
+        *offset += 4 * width;
+        return Ok(bytes.chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect());
+    }
+    if ((header[2] as usize) << 8 | header[3] as usize) != width {
+        return Err(invalid_data("scanline width mismatch"));
+    }
+    *offset += 4;
+    let mut scanline = vec![[0; 4]; width];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(*offset).ok_or_else(|| invalid_data("truncated scanline"))? as usize;
+            *offset += 1;
+            let (run, count) = if count > 128 { (true, count - 128) } else { (false, count) };
+            if count == 0 || x + count > width {
+                return Err(invalid_data("bad scanline run length"));
+            }
+            for pixel in scanline[x..x + count].iter_mut() {
+                pixel[channel] = *data.get(*offset).ok_or_else(|| invalid_data("truncated scanline"))?;
+                if !run {
+                    *offset += 1;
+                }
+            }
+            if run {
+                *offset += 1;
+            }
+            x += count;
+        }
+    }
+    Ok(scanline)
+}
+
+pub fn decode_hdr(data: &[u8]) -> io::Result<(usize, usize, Vec<Color>)> {
+    let mut offset = 0;
+    let mut next_line = || {
+        let end = data[offset..].iter().position(|&byte| byte == b'\n').ok_or_else(|| invalid_data("truncated header"))?;
+        let line = String::from_utf8_lossy(&data[offset..offset + end]).into_owned();
+        offset += end + 1;
+        Ok::<String, io::Error>(line)
+    };
+    let magic = next_line()?;
+    if !magic.starts_with("#?") {
+        return Err(invalid_data("not a Radiance HDR file"));
+    }
+    loop {
+        let line = next_line()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data("only 32-bit_rle_rgbe Radiance files are supported"));
+            }
+        }
+    }
+    let resolution = next_line()?;
+    let (height, width): (usize, usize) = match resolution.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        ["-Y", height, "+X", width] => match (height.parse(), width.parse()) {
+            (Ok(height), Ok(width)) => (height, width),
+            _ => return Err(invalid_data("bad resolution line"))
+        },
+        _ => return Err(invalid_data("only -Y +X Radiance files are supported"))
+    };
+    let mut pixels = Vec::with_capacity(width * height);
+    for _ in 0..height {
+        pixels.extend(read_scanline(data, &mut offset, width)?.into_iter().map(from_rgbe));
+    }
+    Ok((width, height, pixels))
+}
+
+impl ImageEncoder for HdrEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", framebuffer.height(), framebuffer.width())?;
+        let mut data = Vec::with_capacity(framebuffer.pixels().len() * 4);
+        for color in framebuffer.pixels() {
+            data.extend_from_slice(&to_rgbe(*color));
+        }
+        writer.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trips(color: Color) {
+        let decoded = from_rgbe(to_rgbe(color));
+        let max = color.x().max(color.y()).max(color.z());
+        for &(original, decoded) in &[(color.x(), decoded.x()), (color.y(), decoded.y()), (color.z(), decoded.z())] {
+            assert!((decoded - original).abs() <= max / 256.0, "{} decoded as {}", original, decoded);
+        }
+    }
+
+    #[test]
+    fn rgbe_round_trips_within_the_shared_exponent_precision() {
+        for &value in &[1e-30, 1e-3, 0.5, 1.0, 3.7, 255.0, 256.0, 1e10, 1e30, 1e38] {
+            assert_round_trips(Color::new(value, value * 0.5, value * 0.01));
+            assert_round_trips(Color::new(value * 0.3, value * 0.2, value));
+        }
+    }
+
+    #[test]
+    fn rgbe_saturates_colors_beyond_its_range() {
+        let decoded = from_rgbe(to_rgbe(Color::new(f32::MAX, 1e38, 0.0)));
+        assert!(decoded.x() > 1.69e38 && decoded.x().is_finite(), "{}", decoded);
+        assert!((decoded.y() - 1e38).abs() < 1e38 / 256.0, "{}", decoded);
+    }
+
+    #[test]
+    fn rgbe_stores_black_and_negative_values_as_zero() {
+        assert_eq!(to_rgbe(Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(Color::new(-1.0, -2.0, 0.0)), [0, 0, 0, 0]);
+        let decoded = from_rgbe([0, 0, 0, 0]);
+        assert_eq!((decoded.x(), decoded.y(), decoded.z()), (0.0, 0.0, 0.0));
+        assert_eq!(to_rgbe(Color::new(1.0, -1.0, 0.0))[1], 0);
+    }
+
+    #[test]
+    fn encoded_images_decode_to_the_same_pixels() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        for (index, &value) in [0.0, 0.25, 1.0, 7.5, 1e4, 1e-4].iter().enumerate() {
+            framebuffer.set(index % 3, index / 3, Color::new(value, value * 0.5, value * 0.25));
+        }
+        let mut bytes = Vec::new();
+        HdrEncoder.encode(&framebuffer, &mut bytes).unwrap();
+        let (width, height, pixels) = decode_hdr(&bytes).unwrap();
+        assert_eq!((width, height), (3, 2));
+        for (original, decoded) in framebuffer.pixels().iter().zip(&pixels) {
+            assert!((*decoded - *original).length() <= original.x() / 128.0, "{} decoded as {}", original, decoded);
+        }
+    }
+}
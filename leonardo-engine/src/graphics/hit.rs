@@ -1,7 +1,9 @@
-use crate::algebra::vec3::{Point3, Vec3};
+use crate::algebra::onb::Onb;
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::aabb::Aabb;
 use crate::graphics::material::Material;
 use crate::graphics::ray::Ray;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct HittableStore {
     store: Vec<Box<dyn Hittable>>
@@ -19,16 +21,41 @@ impl HittableStore {
     pub fn clear(&mut self) {
         self.store.clear();
     }
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+    pub fn into_vec(self) -> Vec<Box<dyn Hittable>> {
+        self.store
+    }
+    pub fn split_unbounded(self, time0: f32, time1: f32) -> (HittableStore, HittableStore) {
+        let (bounded, unbounded) = self.store.into_iter()
+            .partition(|item| item.bounding_box(time0, time1).is_some());
+        (HittableStore { store: bounded }, HittableStore { store: unbounded })
+    }
 }
 
-impl Hittable for HittableStore {
-    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+impl Default for HittableStore {
+    fn default() -> Self {
+        HittableStore::new()
+    }
+}
+
+impl HittableStore {
+    fn closest<F>(&self, ray: Ray, t_min: f32, t_max: f32, hit: F) -> Option<HitRecord>
+    where
+        F: Fn(&dyn Hittable, Ray, f32, f32, &HitRecord) -> Option<HitRecord>,
+    {
         let mut temp_rec = HitRecord::new_def();
         let mut hit_anything = false;
+        let mut closest_so_far = t_max;
         for item in self.store.iter() {
-            match item.hit(ray, t_min, t_max, &temp_rec) {
+            match hit(item.as_ref(), ray, t_min, closest_so_far, &temp_rec) {
                 Some(new_hit_record) => {
                     hit_anything = true;
+                    closest_so_far = new_hit_record.t;
                     temp_rec = new_hit_record;
                 }
                 _ => continue
@@ -37,7 +64,30 @@ impl Hittable for HittableStore {
         if hit_anything {
             return Some(temp_rec);
         }
-        return None;
+        None
+    }
+}
+
+impl Hittable for HittableStore {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        self.closest(ray, t_min, t_max, |item, ray, t_min, t_max, rec| item.hit(ray, t_min, t_max, rec))
+    }
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        self.closest(ray, t_min, t_max, |item, ray, t_min, t_max, rec| item.hit_surface(ray, t_min, t_max, rec))
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        self.store.iter().map(|item| item.transmittance(ray, t_min, t_max)).product()
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let mut output_box: Option<Aabb> = None;
+        for item in self.store.iter() {
+            let item_box = item.bounding_box(time0, time1)?;
+            output_box = match output_box {
+                Some(current) => Some(Aabb::surrounding(current, item_box)),
+                None => Some(item_box)
+            };
+        }
+        output_box
     }
 }
 
@@ -45,9 +95,17 @@ impl Hittable for HittableStore {
 pub struct HitRecord {
     pub point: Point3,
     pub normal: Vec3,
+    pub shading_normal: Vec3,
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub t: f32,
+    pub u: f32,
+    pub v: f32,
     pub front_face: bool,
-    pub material: Option<Rc<dyn Material>>,
+    pub material: Option<Arc<dyn Material>>,
+    emission: Color,
 }
 
 impl HitRecord {
@@ -56,31 +114,125 @@ impl HitRecord {
             Point3::new(0.0, 0.0, 0.0),
             Vec3::new(0.0, 0.0, 0.0),
             0.0,
+            0.0,
+            0.0,
             false,
             Option::None,
         )
     }
-    pub fn new(point: Point3, normal: Vec3, t: f32, front_face: bool, material: Option<Rc<dyn Material>>) -> HitRecord {
+    pub fn new(point: Point3, normal: Vec3, t: f32, u: f32, v: f32, front_face: bool, material: Option<Arc<dyn Material>>) -> HitRecord {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
         HitRecord {
             point,
             normal,
+            shading_normal: normal,
+            tangent: zero,
+            bitangent: zero,
+            dpdu: zero,
+            dpdv: zero,
             t,
+            u,
+            v,
             front_face,
             material,
-        }
+            emission: zero,
+        }.oriented()
     }
     pub fn with_normal(hit_record: HitRecord, ray: Ray, outward_normal: Vec3) -> HitRecord {
         let front_face = outward_normal.dot(ray.direction()) < 0.0;
+        let normal = if front_face { outward_normal } else { -outward_normal };
         HitRecord {
-            point: hit_record.point,
-            normal: if front_face { outward_normal } else { -outward_normal },
-            t: hit_record.t,
+            normal,
+            shading_normal: normal,
             front_face,
-            material: hit_record.material,
+            ..hit_record
+        }.oriented()
+    }
+    pub fn with_derivatives(self, dpdu: Vec3, dpdv: Vec3) -> HitRecord {
+        HitRecord {
+            dpdu,
+            dpdv,
+            ..self
+        }.oriented()
+    }
+    pub(crate) fn with_emission(self, emission: Color) -> HitRecord {
+        HitRecord {
+            emission,
+            ..self
+        }
+    }
+    pub(crate) fn emission(&self) -> Color {
+        self.emission
+    }
+    pub fn with_shading_normal(self, shading_normal: Vec3) -> HitRecord {
+        let shading_normal = Vec3::unit_vector(shading_normal);
+        HitRecord {
+            shading_normal: if shading_normal.dot(self.normal) < 0.0 { -shading_normal } else { shading_normal },
+            ..self
+        }.oriented()
+    }
+    pub fn shading_frame(&self) -> Onb {
+        Onb::from_axes(self.tangent, self.bitangent, self.shading_normal)
+    }
+    pub fn leaks(&self, direction: Vec3) -> bool {
+        (direction.dot(self.normal) > 0.0) != (direction.dot(self.shading_normal) > 0.0)
+    }
+    fn oriented(mut self) -> HitRecord {
+        if self.shading_normal.length_square() == 0.0 {
+            return self;
+        }
+        let tangent = self.dpdu - self.shading_normal * self.dpdu.dot(self.shading_normal);
+        self.tangent = if tangent.length_square() > 1e-12 {
+            Vec3::unit_vector(tangent)
+        } else {
+            Onb::new(self.shading_normal).u()
+        };
+        self.bitangent = self.shading_normal.cross(self.tangent);
+        if self.bitangent.dot(self.dpdv) < 0.0 {
+            self.bitangent = -self.bitangent;
+        }
+        if self.dpdu.length_square() == 0.0 {
+            self.dpdu = self.tangent;
+            self.dpdv = self.bitangent;
         }
+        self
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord>;
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb>;
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.hit(ray, t_min, t_max, hit_record)
+    }
+    fn transmittance(&self, _ray: Ray, _t_min: f32, _t_max: f32) -> f32 {
+        1.0
+    }
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f32 {
+        0.0
+    }
+    fn random(&self, _origin: Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.as_ref().hit(ray, t_min, t_max, hit_record)
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.as_ref().bounding_box(time0, time1)
+    }
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.as_ref().hit_surface(ray, t_min, t_max, hit_record)
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        self.as_ref().transmittance(ray, t_min, t_max)
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        self.as_ref().pdf_value(origin, direction)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.as_ref().random(origin)
+    }
 }
\ No newline at end of file
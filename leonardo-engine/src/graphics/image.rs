@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::algebra::vec3::Color;
+use crate::graphics::exr::{ExrEncoder, ExrPrecision};
+use crate::graphics::hdr::HdrEncoder;
+use crate::graphics::png::PngEncoder;
+use crate::graphics::ppm::{PpmAsciiEncoder, PpmBinaryEncoder};
+use crate::graphics::tonemap::DisplayTransform;
+
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Color::new(0.0, 0.0, 0.0); width * height],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ImageFormat, transform: DisplayTransform) -> io::Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|err| io::Error::new(
+            err.kind(),
+            format!("could not create {}: {}", path.display(), err),
+        ))?;
+        let mut writer = BufWriter::new(file);
+        format.encoder(transform).encode(self, &mut writer)?;
+        writer.flush()
+    }
+}
+
+pub trait ImageEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    PpmAscii,
+    PpmBinary,
+    Png8,
+    Png16,
+    ExrHalf,
+    ExrFloat,
+    Hdr,
+}
+
+impl ImageFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::PpmAscii),
+            "png" => Some(ImageFormat::Png8),
+            "exr" => Some(ImageFormat::ExrFloat),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None
+        }
+    }
+    pub fn is_hdr(self) -> bool {
+        matches!(self, ImageFormat::ExrHalf | ImageFormat::ExrFloat | ImageFormat::Hdr)
+    }
+    pub fn encoder(self, transform: DisplayTransform) -> Box<dyn ImageEncoder> {
+        match self {
+            ImageFormat::PpmAscii => Box::new(PpmAsciiEncoder::new(transform)),
+            ImageFormat::PpmBinary => Box::new(PpmBinaryEncoder::new(transform)),
+            ImageFormat::Png8 => Box::new(PngEncoder::new(8, transform)),
+            ImageFormat::Png16 => Box::new(PngEncoder::new(16, transform)),
+            ImageFormat::ExrHalf => Box::new(ExrEncoder::new(ExrPrecision::Half)),
+            ImageFormat::ExrFloat => Box::new(ExrEncoder::new(ExrPrecision::Float)),
+            ImageFormat::Hdr => Box::new(HdrEncoder),
+        }
+    }
+}
+
+pub fn quantize_8(x: f32) -> u8 {
+    (x.clamp(0.0, 0.999) * 256.0) as u8
+}
+
+pub fn quantize_16(x: f32) -> u16 {
+    (x.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantize_8_clamps_to_the_byte_range() {
+        assert_eq!(quantize_8(-1.0), 0);
+        assert_eq!(quantize_8(0.0), 0);
+        assert_eq!(quantize_8(0.5), 128);
+        assert_eq!(quantize_8(1.0), 255);
+        assert_eq!(quantize_8(100.0), 255);
+    }
+
+    #[test]
+    fn quantize_16_clamps_to_the_word_range() {
+        assert_eq!(quantize_16(-1.0), 0);
+        assert_eq!(quantize_16(0.0), 0);
+        assert_eq!(quantize_16(0.5), 32768);
+        assert_eq!(quantize_16(1.0), 65535);
+        assert_eq!(quantize_16(100.0), 65535);
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::background::Background;
+use crate::graphics::hit::Hittable;
+
+pub struct LightList {
+    lights: Vec<Arc<dyn Hittable>>,
+    environment: Option<Arc<dyn Background>>,
+}
+
+impl LightList {
+    pub fn new() -> LightList {
+        LightList {
+            lights: Vec::new(),
+            environment: None,
+        }
+    }
+    pub fn add(&mut self, light: Arc<dyn Hittable>) {
+        self.lights.push(light);
+    }
+    pub fn set_environment(&mut self, environment: Arc<dyn Background>) {
+        self.environment = if environment.is_sampled() { Some(environment) } else { None };
+    }
+    pub fn environment(&self) -> Option<&dyn Background> {
+        self.environment.as_deref()
+    }
+    pub fn len(&self) -> usize {
+        self.lights.len() + self.environment.is_some() as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self.lights.iter().map(|light| light.pdf_value(origin, direction)).sum();
+        let environment = self.environment.as_ref().map_or(0.0, |environment| environment.pdf_value(direction));
+        (sum + environment) / self.len() as f32
+    }
+    pub fn random(&self, origin: Point3) -> Vec3 {
+        let index = ((random_f32() * self.len() as f32) as usize).min(self.len() - 1);
+        match self.lights.get(index) {
+            Some(light) => light.random(origin),
+            None => self.environment.as_ref().map_or(Vec3::new(0.0, 1.0, 0.0), |environment| environment.random())
+        }
+    }
+}
+
+impl Default for LightList {
+    fn default() -> Self {
+        LightList::new()
+    }
+}
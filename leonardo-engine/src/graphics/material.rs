@@ -1,49 +1,102 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algebra::onb::Onb;
 use crate::algebra::utility::random_f32;
 use crate::algebra::vec3::{Color, Vec3};
 use crate::graphics::hit::HitRecord;
+use crate::graphics::microfacet::{self, fresnel_conductor, fresnel_dielectric, mirror, Ggx};
 use crate::graphics::ray::Ray;
+use crate::graphics::texture::{SolidColor, Texture};
 
 pub struct Scatter {
     pub attenuation: Color,
     pub scattered: Ray,
     pub is_scattered: bool,
+    pub is_specular: bool,
+    pub pdf: f32,
 }
 
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(&self, ray: Ray, hit_record: &HitRecord, attenuation: Color, scattered: Ray) -> Scatter;
+    fn sample(&self, ray: Ray, hit_record: &HitRecord) -> Scatter {
+        self.scatter(ray, hit_record, Color::new(0.0, 0.0, 0.0), ray)
+    }
+    fn emitted(&self, _ray: Ray, _hit_record: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+    fn eval(&self, _ray: Ray, _hit_record: &HitRecord, _direction: Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+    fn pdf(&self, _ray: Ray, _hit_record: &HitRecord, _direction: Vec3) -> f32 {
+        0.0
+    }
+}
+
+fn cosine_pdf(normal: Vec3, direction: Vec3) -> f32 {
+    let cosine = normal.dot(Vec3::unit_vector(direction));
+    if cosine <= 0.0 {
+        return 0.0;
+    }
+    cosine / PI
 }
 
 pub struct Lambertian {
-    albedo: Color
+    albedo: Arc<dyn Texture>
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Lambertian {
+        Lambertian::textured(Arc::new(SolidColor::new(albedo)))
+    }
+    pub fn textured(albedo: Arc<dyn Texture>) -> Lambertian {
         Lambertian {
             albedo
         }
     }
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        self.albedo.value(hit_record.u, hit_record.v, hit_record.point)
+    }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray: Ray, hit_record: &HitRecord, attenuation: Color, scattered: Ray) -> Scatter {
-        let scatter_dir = hit_record.normal + Vec3::random_unit_vector();
-        let scattered_ray = Ray::new(hit_record.point, scatter_dir);
-        return Scatter {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let scatter_dir = hit_record.shading_normal + Vec3::random_unit_vector();
+        let scattered_ray = Ray::with_time(hit_record.point, scatter_dir, ray.time());
+        Scatter {
             scattered: scattered_ray,
-            attenuation: self.albedo,
+            attenuation: self.albedo(hit_record),
             is_scattered: true,
-        };
+            is_specular: false,
+            pdf: cosine_pdf(hit_record.shading_normal, scatter_dir),
+        }
+    }
+    fn eval(&self, _ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.albedo(hit_record) * cosine_pdf(hit_record.shading_normal, direction)
+    }
+    fn pdf(&self, _ray: Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        cosine_pdf(hit_record.shading_normal, direction)
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzz: f32,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f32) -> Metal {
+        Metal::textured(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: f32) -> Metal {
         Metal {
             albedo,
             fuzz: if fuzz < 1.0 { fuzz } else { 1.0 },
@@ -52,15 +105,17 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: Ray, hit_record: &HitRecord, attenuation: Color, scattered: Ray) -> Scatter {
-        let reflected = reflect(Vec3::unit_vector(ray.direction()), hit_record.normal);
-        let scattered_ray = Ray::new(hit_record.point, reflected + Vec3::random_in_unit_sphere() * self.fuzz);
-        let is_scattered = scattered_ray.direction().dot(hit_record.normal) > 0.0;
-        return Scatter {
-            attenuation: self.albedo,
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let reflected = reflect(Vec3::unit_vector(ray.direction()), hit_record.shading_normal);
+        let scattered_ray = Ray::with_time(hit_record.point, reflected + Vec3::random_in_unit_sphere() * self.fuzz, ray.time());
+        let is_scattered = scattered_ray.direction().dot(hit_record.shading_normal) > 0.0;
+        Scatter {
+            attenuation: self.albedo.value(hit_record.u, hit_record.v, hit_record.point),
             scattered: scattered_ray,
             is_scattered,
-        };
+            is_specular: true,
+            pdf: 0.0,
+        }
     }
 }
 
@@ -80,44 +135,515 @@ pub fn schlick_approx(cos: f32, ref_idx: f32) -> f32 {
     r0 + (1.0 - r0) * (1.0 - cos).powi(5)
 }
 
+const REFERENCE_WAVELENGTH: f32 = 587.6;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Ior {
+    Constant(f32),
+    Cauchy {
+        a: f32,
+        b: f32,
+    },
+    Sellmeier {
+        b: [f32; 3],
+        c: [f32; 3],
+    },
+}
+
+impl Ior {
+    pub fn at(&self, wavelength: Option<f32>) -> f32 {
+        let micrometers = wavelength.unwrap_or(REFERENCE_WAVELENGTH) / 1000.0;
+        let lambda_square = micrometers * micrometers;
+        match *self {
+            Ior::Constant(ior) => ior,
+            Ior::Cauchy { a, b } => a + b / lambda_square,
+            Ior::Sellmeier { b, c } => {
+                let sum: f32 = (0..3).map(|i| b[i] * lambda_square / (lambda_square - c[i])).sum();
+                (1.0 + sum).max(1e-4).sqrt()
+            }
+        }
+    }
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
+
 pub struct Dielectric {
-    pub ref_idx: f32
+    pub ior: Ior
 }
 
 impl Dielectric {
     pub fn new(ref_idx: f32) -> Dielectric {
-        Dielectric { ref_idx }
+        Dielectric::dispersive(Ior::Constant(ref_idx))
+    }
+    pub fn dispersive(ior: Ior) -> Dielectric {
+        Dielectric { ior }
     }
 }
 
-fn get_reflected(unit_direction: Vec3, hit_record: &HitRecord) -> Scatter {
-    let reflected = reflect(unit_direction, hit_record.normal);
+fn get_reflected(ray: Ray, unit_direction: Vec3, hit_record: &HitRecord) -> Scatter {
+    let reflected = reflect(unit_direction, hit_record.shading_normal);
     Scatter {
-        scattered: Ray::new(hit_record.point, reflected),
+        scattered: Ray::with_time(hit_record.point, reflected, ray.time()),
         attenuation: Color::new(1.0, 1.0, 1.0),
         is_scattered: true,
+        is_specular: true,
+        pdf: 0.0,
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: Ray, hit_record: &HitRecord, attenuation: Color, scattered: Ray) -> Scatter {
-        let etai_over_etat = if hit_record.front_face { 1.0 / self.ref_idx } else { self.ref_idx };
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let ref_idx = self.ior.at(ray.wavelength());
+        let etai_over_etat = if hit_record.front_face { 1.0 / ref_idx } else { ref_idx };
         let unit_direction = Vec3::unit_vector(ray.direction());
-        let cos_theta = 1.0_f32.min((-unit_direction).dot(hit_record.normal));
+        let cos_theta = 1.0_f32.min((-unit_direction).dot(hit_record.shading_normal));
         let sin_theta = (1.0 - (cos_theta * cos_theta)).sqrt();
         if etai_over_etat * sin_theta > 1.0 {
-            return get_reflected(unit_direction, hit_record);
+            return get_reflected(ray, unit_direction, hit_record);
         }
         let reflect_prob = schlick_approx(cos_theta, etai_over_etat);
-        if random_f32(rand::thread_rng()) < reflect_prob {
-            return get_reflected(unit_direction, hit_record);
+        if random_f32() < reflect_prob {
+            return get_reflected(ray, unit_direction, hit_record);
         }
 
-        let refracted = refract(unit_direction, hit_record.normal, etai_over_etat);
-        return Scatter {
+        let refracted = refract(unit_direction, hit_record.shading_normal, etai_over_etat);
+        Scatter {
             attenuation: Color::new(1.0, 1.0, 1.0),
-            scattered: Ray::new(hit_record.point, refracted),
+            scattered: Ray::with_time(hit_record.point, refracted, ray.time()),
             is_scattered: true,
+            is_specular: true,
+            pdf: 0.0,
+        }
+    }
+    fn is_dispersive(&self) -> bool {
+        self.ior.is_dispersive()
+    }
+}
+
+fn absorbed(ray: Ray) -> Scatter {
+    Scatter {
+        attenuation: Color::new(0.0, 0.0, 0.0),
+        scattered: ray,
+        is_scattered: false,
+        is_specular: false,
+        pdf: 0.0,
+    }
+}
+
+fn local_directions(ray: Ray, hit_record: &HitRecord, direction: Vec3) -> (Onb, Vec3, Vec3) {
+    let frame = hit_record.shading_frame();
+    let wo = frame.coordinates(-Vec3::unit_vector(ray.direction()));
+    let wi = frame.coordinates(Vec3::unit_vector(direction));
+    (frame, wo, wi)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConductorPreset {
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+}
+
+impl ConductorPreset {
+    pub fn eta(self) -> Color {
+        match self {
+            ConductorPreset::Gold => Color::new(0.143, 0.374, 1.442),
+            ConductorPreset::Copper => Color::new(0.200, 0.924, 1.102),
+            ConductorPreset::Aluminium => Color::new(1.657, 0.880, 0.521),
+            ConductorPreset::Silver => Color::new(0.155, 0.117, 0.138),
+        }
+    }
+    pub fn k(self) -> Color {
+        match self {
+            ConductorPreset::Gold => Color::new(3.983, 2.385, 1.603),
+            ConductorPreset::Copper => Color::new(3.912, 2.452, 2.142),
+            ConductorPreset::Aluminium => Color::new(9.224, 6.270, 4.837),
+            ConductorPreset::Silver => Color::new(4.828, 3.122, 2.147),
+        }
+    }
+}
+
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    roughness: f32,
+    distribution: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f32) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            roughness,
+            distribution: Ggx::new(roughness, 0.0),
+        }
+    }
+    pub fn preset(preset: ConductorPreset, roughness: f32) -> Conductor {
+        Conductor::new(preset.eta(), preset.k(), roughness)
+    }
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Conductor {
+        self.distribution = Ggx::new(self.roughness, anisotropy);
+        self
+    }
+    fn local_eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        let reflection = self.distribution.reflection(wo, wi);
+        if reflection <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        fresnel_conductor(wo.dot(Vec3::unit_vector(wo + wi)), self.eta, self.k) * reflection
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let (frame, wo, _) = local_directions(ray, hit_record, hit_record.shading_normal);
+        if wo.z() <= 0.0 {
+            return absorbed(ray);
+        }
+        if self.distribution.is_smooth() {
+            return Scatter {
+                attenuation: fresnel_conductor(wo.z(), self.eta, self.k),
+                scattered: Ray::with_time(hit_record.point, frame.local(Vec3::new(-wo.x(), -wo.y(), wo.z())), ray.time()),
+                is_scattered: true,
+                is_specular: true,
+                pdf: 0.0,
+            };
+        }
+        let wi = match self.distribution.sample_reflection(wo) {
+            Some(wi) => wi,
+            None => return absorbed(ray)
+        };
+        let pdf = self.distribution.reflection_pdf(wo, wi);
+        Scatter {
+            attenuation: self.local_eval(wo, wi) / pdf,
+            scattered: Ray::with_time(hit_record.point, frame.local(wi), ray.time()),
+            is_scattered: true,
+            is_specular: false,
+            pdf,
+        }
+    }
+    fn eval(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let (_, wo, wi) = local_directions(ray, hit_record, direction);
+        if self.distribution.is_smooth() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.local_eval(wo, wi)
+    }
+    fn pdf(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        let (_, wo, wi) = local_directions(ray, hit_record, direction);
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        self.distribution.reflection_pdf(wo, wi)
+    }
+}
+
+pub struct RoughDielectric {
+    ior: Ior,
+    roughness: f32,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(ref_idx: f32, roughness: f32) -> RoughDielectric {
+        RoughDielectric::dispersive(Ior::Constant(ref_idx), roughness)
+    }
+    pub fn dispersive(ior: Ior, roughness: f32) -> RoughDielectric {
+        RoughDielectric {
+            ior,
+            roughness,
+            distribution: Ggx::new(roughness, 0.0),
+        }
+    }
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> RoughDielectric {
+        self.distribution = Ggx::new(self.roughness, anisotropy);
+        self
+    }
+    fn eta(&self, ray: Ray, hit_record: &HitRecord) -> f32 {
+        let ref_idx = self.ior.at(ray.wavelength());
+        if hit_record.front_face { ref_idx } else { 1.0 / ref_idx }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let (frame, wo, _) = local_directions(ray, hit_record, hit_record.shading_normal);
+        if wo.z() <= 0.0 {
+            return absorbed(ray);
+        }
+        let eta = self.eta(ray, hit_record);
+        if self.distribution.is_smooth() {
+            let h = Vec3::new(0.0, 0.0, 1.0);
+            let wi = if random_f32() < fresnel_dielectric(wo.z(), eta) { mirror(wo, h) } else { microfacet::refract(wo, h, eta) };
+            return Scatter {
+                attenuation: Color::new(1.0, 1.0, 1.0),
+                scattered: Ray::with_time(hit_record.point, frame.local(wi), ray.time()),
+                is_scattered: true,
+                is_specular: true,
+                pdf: 0.0,
+            };
+        }
+        let wi = match self.distribution.sample_dielectric(wo, eta) {
+            Some(wi) => wi,
+            None => return absorbed(ray)
         };
+        let pdf = self.distribution.dielectric_pdf(wo, wi, eta);
+        if pdf <= 0.0 {
+            return absorbed(ray);
+        }
+        let weight = self.distribution.dielectric(wo, wi, eta) / pdf;
+        Scatter {
+            attenuation: Color::new(weight, weight, weight),
+            scattered: Ray::with_time(hit_record.point, frame.local(wi), ray.time()),
+            is_scattered: true,
+            is_specular: false,
+            pdf,
+        }
     }
-}
\ No newline at end of file
+    fn eval(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let (_, wo, wi) = local_directions(ray, hit_record, direction);
+        if self.distribution.is_smooth() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let value = self.distribution.dielectric(wo, wi, self.eta(ray, hit_record));
+        Color::new(value, value, value)
+    }
+    fn pdf(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        let (_, wo, wi) = local_directions(ray, hit_record, direction);
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        self.distribution.dielectric_pdf(wo, wi, self.eta(ray, hit_record))
+    }
+    fn is_dispersive(&self) -> bool {
+        self.ior.is_dispersive()
+    }
+}
+
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+    two_sided: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight::textured(Arc::new(SolidColor::new(emit)))
+    }
+    pub fn textured(emit: Arc<dyn Texture>) -> DiffuseLight {
+        DiffuseLight {
+            emit,
+            two_sided: false,
+        }
+    }
+    pub fn with_two_sided(mut self, two_sided: bool) -> DiffuseLight {
+        self.two_sided = two_sided;
+        self
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, ray: Ray, _hit_record: &HitRecord, attenuation: Color, _scattered: Ray) -> Scatter {
+        Scatter {
+            attenuation,
+            scattered: ray,
+            is_scattered: false,
+            is_specular: false,
+            pdf: 0.0,
+        }
+    }
+    fn emitted(&self, _ray: Ray, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face || self.two_sided {
+            return self.emit.value(hit_record.u, hit_record.v, hit_record.point);
+        }
+        Color::new(0.0, 0.0, 0.0)
+    }
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.max(0.0).sqrt())
+}
+
+pub struct Isotropic {
+    albedo: Arc<dyn Texture>
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic::textured(Arc::new(SolidColor::new(albedo)))
+    }
+    pub fn textured(albedo: Arc<dyn Texture>) -> Isotropic {
+        Isotropic {
+            albedo
+        }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        Scatter {
+            attenuation: self.albedo.value(hit_record.u, hit_record.v, hit_record.point),
+            scattered: Ray::with_time(hit_record.point, Vec3::random_unit_vector(), ray.time()),
+            is_scattered: true,
+            is_specular: false,
+            pdf: 1.0 / (4.0 * PI),
+        }
+    }
+    fn eval(&self, _ray: Ray, hit_record: &HitRecord, _direction: Vec3) -> Color {
+        self.albedo.value(hit_record.u, hit_record.v, hit_record.point) / (4.0 * PI)
+    }
+    fn pdf(&self, _ray: Ray, _hit_record: &HitRecord, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Texture>,
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f32) -> HenyeyGreenstein {
+        HenyeyGreenstein::textured(Arc::new(SolidColor::new(albedo)), g)
+    }
+    pub fn textured(albedo: Arc<dyn Texture>, g: f32) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+    fn sample_cos_theta(&self) -> f32 {
+        let xi = random_f32();
+        if self.g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+        let s = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * xi);
+        ((1.0 + self.g * self.g - s * s) / (2.0 * self.g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_f32();
+        let direction = Onb::new(ray.direction()).local(Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta));
+        Scatter {
+            attenuation: self.albedo.value(hit_record.u, hit_record.v, hit_record.point),
+            scattered: Ray::with_time(hit_record.point, direction, ray.time()),
+            is_scattered: true,
+            is_specular: false,
+            pdf: henyey_greenstein(cos_theta, self.g),
+        }
+    }
+    fn eval(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.albedo.value(hit_record.u, hit_record.v, hit_record.point) * self.pdf(ray, hit_record, direction)
+    }
+    fn pdf(&self, ray: Ray, _hit_record: &HitRecord, direction: Vec3) -> f32 {
+        let cos_theta = Vec3::unit_vector(ray.direction()).dot(Vec3::unit_vector(direction));
+        henyey_greenstein(cos_theta, self.g)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+    use crate::algebra::vec3::Point3;
+
+    const SAMPLES: usize = 50_000;
+
+    fn incoming(cos_theta: f32) -> Ray {
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(sin_theta, 0.0, -cos_theta))
+    }
+
+    fn surface(front_face: bool) -> HitRecord {
+        HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, 0.5, 0.5, front_face, None)
+    }
+
+    fn furnace_albedo(material: &dyn Material, cos_theta: f32, front_face: bool) -> Color {
+        let (ray, hit_record) = (incoming(cos_theta), surface(front_face));
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..SAMPLES {
+            let scatter = material.sample(ray, &hit_record);
+            if scatter.is_scattered {
+                sum = sum + scatter.attenuation;
+            }
+        }
+        sum / SAMPLES as f32
+    }
+
+    fn white_conductor(roughness: f32) -> Conductor {
+        Conductor::new(Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0), roughness)
+    }
+
+    fn rough_materials() -> Vec<(&'static str, Box<dyn Material>, bool)> {
+        vec![
+            ("conductor", Box::new(white_conductor(0.3)), true),
+            ("rough conductor", Box::new(white_conductor(0.8)), true),
+            ("anisotropic conductor", Box::new(white_conductor(0.5).with_anisotropy(0.7)), true),
+            ("dielectric", Box::new(RoughDielectric::new(1.5, 0.3)), true),
+            ("dielectric from inside", Box::new(RoughDielectric::new(1.5, 0.3)), false),
+            ("rough dielectric", Box::new(RoughDielectric::new(1.5, 0.8)), true),
+            ("anisotropic dielectric", Box::new(RoughDielectric::new(1.5, 0.5).with_anisotropy(-0.7)), false),
+        ]
+    }
+
+    #[test]
+    fn white_furnace_does_not_create_energy() {
+        seed_rng(7);
+        for (name, material, front_face) in rough_materials() {
+            for &cos_theta in &[1.0, 0.7, 0.3, 0.05] {
+                let albedo = furnace_albedo(material.as_ref(), cos_theta, front_face);
+                for channel in 0..3 {
+                    assert!(albedo[channel] <= 1.01, "{} reflects {} at cos {}", name, albedo, cos_theta);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn white_furnace_keeps_smooth_lobes_nearly_lossless() {
+        seed_rng(11);
+        let materials: [(&str, Box<dyn Material>, bool); 3] = [
+            ("conductor", Box::new(white_conductor(0.2)), true),
+            ("dielectric", Box::new(RoughDielectric::new(1.5, 0.1)), true),
+            ("dielectric from inside", Box::new(RoughDielectric::new(1.5, 0.1)), false),
+        ];
+        for (name, material, front_face) in materials.iter() {
+            let albedo = furnace_albedo(material.as_ref(), 0.8, *front_face);
+            for channel in 0..3 {
+                assert!(albedo[channel] >= 0.95, "{} only reflects {}", name, albedo);
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_weights_match_eval_over_pdf() {
+        seed_rng(3);
+        let ray = incoming(0.8);
+        for (name, material, front_face) in rough_materials() {
+            let hit_record = surface(front_face);
+            for _ in 0..1000 {
+                let scatter = material.sample(ray, &hit_record);
+                if !scatter.is_scattered {
+                    continue;
+                }
+                assert!(!scatter.is_specular, "{}", name);
+                let direction = scatter.scattered.direction();
+                let pdf = material.pdf(ray, &hit_record, direction);
+                assert!((pdf - scatter.pdf).abs() <= 1e-3 * pdf.max(1.0), "{}: {} != {}", name, pdf, scatter.pdf);
+                let expected = material.eval(ray, &hit_record, direction) / pdf;
+                assert!((expected - scatter.attenuation).length() <= 1e-3 * expected.length().max(1.0), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::{Material, Scatter};
+use crate::graphics::ray::Ray;
+use crate::graphics::spectrum::blackbody;
+use crate::graphics::volume::GridVolume;
+
+const SHELL_EPSILON: f32 = 1e-4;
+const BLACKBODY_TABLE_SIZE: usize = 256;
+const ROULETTE_THRESHOLD: f32 = 0.1;
+
+fn collision(point: Point3, t: f32, material: Arc<dyn Material>) -> HitRecord {
+    HitRecord::new(point, Vec3::new(1.0, 0.0, 0.0), t, 0.0, 0.0, true, Some(material))
+}
+
+fn free_flight(rate: f32) -> f32 {
+    -(1.0 - random_f32()).ln() / rate
+}
+
+pub struct ConstantMedium<H: Hittable + ?Sized> {
+    boundary: Arc<H>,
+    density: f32,
+    phase: Arc<dyn Material>,
+}
+
+impl<H: Hittable + ?Sized> ConstantMedium<H> {
+    pub fn new(boundary: Arc<H>, density: f32, phase: Arc<dyn Material>) -> ConstantMedium<H> {
+        ConstantMedium {
+            boundary,
+            density,
+            phase,
+        }
+    }
+    fn interval(&self, ray: Ray, t: f32, t_max: f32) -> Option<(f32, f32)> {
+        let hit_record = HitRecord::new_def();
+        let first = self.boundary.hit_surface(ray, t, f32::MAX, &hit_record)?;
+        let (enter, exit) = if first.front_face {
+            let exit = self.boundary.hit_surface(ray, first.t + SHELL_EPSILON, f32::MAX, &hit_record)?;
+            (first.t, exit.t)
+        } else {
+            (t, first.t)
+        };
+        if enter >= t_max {
+            return None;
+        }
+        Some((enter, exit.min(t_max)))
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for ConstantMedium<H> {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let speed = ray.direction().length();
+        let mut remaining = free_flight(self.density);
+        let mut t = t_min;
+        while t < t_max {
+            let (enter, exit) = self.interval(ray, t, t_max)?;
+            let length = (exit - enter) * speed;
+            if remaining < length {
+                let t = enter + remaining / speed;
+                return Some(collision(ray.at(t), t, self.phase.clone()));
+            }
+            remaining -= length;
+            t = exit;
+        }
+        None
+    }
+    fn hit_surface(&self, _ray: Ray, _t_min: f32, _t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        None
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        let speed = ray.direction().length();
+        let mut length = 0.0;
+        let mut t = t_min;
+        while t < t_max {
+            let Some((enter, exit)) = self.interval(ray, t, t_max) else {
+                break;
+            };
+            length += (exit - enter) * speed;
+            t = exit;
+        }
+        (-self.density * length).exp()
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(time0, time1)
+    }
+}
+
+struct EmissiveCollision {
+    phase: Arc<dyn Material>,
+}
+
+impl Material for EmissiveCollision {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, attenuation: Color, scattered: Ray) -> Scatter {
+        self.phase.scatter(ray, hit_record, attenuation, scattered)
+    }
+    fn emitted(&self, _ray: Ray, hit_record: &HitRecord) -> Color {
+        hit_record.emission()
+    }
+    fn eval(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        self.phase.eval(ray, hit_record, direction)
+    }
+    fn pdf(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        self.phase.pdf(ray, hit_record, direction)
+    }
+}
+
+pub struct GridMedium {
+    volume: Arc<GridVolume>,
+    bounds: Aabb,
+    density_scale: f32,
+    emission: f32,
+    phase: Arc<dyn Material>,
+    emissive_phase: Arc<dyn Material>,
+    blackbody_table: Vec<Color>,
+    max_temperature: f32,
+}
+
+impl GridMedium {
+    pub fn new(volume: Arc<GridVolume>, bounds: Aabb, phase: Arc<dyn Material>) -> GridMedium {
+        let max_temperature = volume.temperature.as_ref().map_or(0.0, |temperature| temperature.max_value());
+        let blackbody_table = if max_temperature > 0.0 {
+            (0..BLACKBODY_TABLE_SIZE)
+                .map(|i| blackbody(max_temperature * i as f32 / (BLACKBODY_TABLE_SIZE - 1) as f32))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        GridMedium {
+            volume,
+            bounds,
+            density_scale: 1.0,
+            emission: 1.0,
+            emissive_phase: Arc::new(EmissiveCollision { phase: phase.clone() }),
+            phase,
+            blackbody_table,
+            max_temperature,
+        }
+    }
+    pub fn with_density_scale(mut self, density_scale: f32) -> GridMedium {
+        self.density_scale = density_scale;
+        self
+    }
+    pub fn with_emission(mut self, emission: f32) -> GridMedium {
+        self.emission = emission;
+        self
+    }
+    fn grid_point(&self, point: Point3) -> Point3 {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        let extent = max - min;
+        let offset = point - min;
+        Point3::new(offset.x() / extent.x(), offset.y() / extent.y(), offset.z() / extent.z())
+    }
+    fn density(&self, grid_point: Point3) -> f32 {
+        self.density_scale * self.volume.density.sample(grid_point)
+    }
+    fn majorant(&self) -> f32 {
+        self.density_scale * self.volume.density.max_value()
+    }
+    fn radiance(&self, grid_point: Point3) -> Color {
+        let temperature = match &self.volume.temperature {
+            Some(temperature) if self.emission > 0.0 && self.max_temperature > 0.0 => temperature.sample(grid_point),
+            _ => return Color::new(0.0, 0.0, 0.0)
+        };
+        let position = temperature / self.max_temperature * (BLACKBODY_TABLE_SIZE - 1) as f32;
+        let index = (position as usize).min(BLACKBODY_TABLE_SIZE - 2);
+        let weight = (position - index as f32).clamp(0.0, 1.0);
+        (self.blackbody_table[index] * (1.0 - weight) + self.blackbody_table[index + 1] * weight) * self.emission
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let (enter, exit) = self.bounds.clip(ray, t_min, t_max)?;
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return None;
+        }
+        let rate = majorant * ray.direction().length();
+        let mut t = enter;
+        loop {
+            t += free_flight(rate);
+            if t >= exit {
+                return None;
+            }
+            let point = ray.at(t);
+            let grid_point = self.grid_point(point);
+            if random_f32() * majorant < self.density(grid_point) {
+                let radiance = self.radiance(grid_point);
+                if radiance.length_square() > 0.0 {
+                    return Some(collision(point, t, self.emissive_phase.clone()).with_emission(radiance));
+                }
+                return Some(collision(point, t, self.phase.clone()));
+            }
+        }
+    }
+    fn hit_surface(&self, _ray: Ray, _t_min: f32, _t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        None
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        let Some((enter, exit)) = self.bounds.clip(ray, t_min, t_max) else {
+            return 1.0;
+        };
+        let majorant = self.majorant();
+        if majorant <= 0.0 {
+            return 1.0;
+        }
+        let rate = majorant * ray.direction().length();
+        let mut transmittance = 1.0;
+        let mut t = enter;
+        loop {
+            t += free_flight(rate);
+            if t >= exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density(self.grid_point(ray.at(t))) / majorant;
+            if transmittance < ROULETTE_THRESHOLD {
+                if random_f32() < 0.5 {
+                    return 0.0;
+                }
+                transmittance *= 2.0;
+            }
+        }
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
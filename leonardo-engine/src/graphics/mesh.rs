@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::bvh::BvhNode;
+use crate::graphics::hit::{HitRecord, Hittable, HittableStore};
+use crate::graphics::material::{Dielectric, Lambertian, Material, Metal};
+use crate::graphics::ray::Ray;
+use crate::graphics::texture::ImageTexture;
+use crate::graphics::triangle::{Displacement, Triangle};
+
+const NORMAL_EPSILON: f32 = 1e-6;
+
+pub struct Mesh {
+    bvh: BvhNode,
+    triangle_count: usize,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Mesh {
+        let triangle_count = triangles.len();
+        let mut store = HittableStore::new();
+        for triangle in triangles {
+            store.store(Box::new(triangle));
+        }
+        Mesh {
+            bvh: BvhNode::new(store, 0.0, 0.0),
+            triangle_count,
+        }
+    }
+    pub fn load<P: AsRef<Path>>(path: P, material: Option<Arc<dyn Material>>) -> io::Result<Mesh> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let (triangles, _) = parse_obj(path, &source, material)?;
+        Ok(Mesh::new(triangles))
+    }
+    pub fn load_displaced<P: AsRef<Path>>(path: P, material: Option<Arc<dyn Material>>, displacement: &Displacement) -> io::Result<Mesh> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let (triangles, corners) = parse_obj(path, &source, material)?;
+        let mut vertex_normals: HashMap<usize, (Vec3, f32)> = HashMap::new();
+        for (triangle, indices) in triangles.iter().zip(&corners) {
+            let area_normal = face_normal(triangle);
+            for index in indices {
+                let (normal, area) = vertex_normals.entry(*index).or_insert((Vec3::new(0.0, 0.0, 0.0), 0.0));
+                *normal = *normal + area_normal;
+                *area += area_normal.length();
+            }
+        }
+        let triangles = triangles.iter()
+            .zip(&corners)
+            .filter(|(triangle, _)| face_normal(triangle).length_square() > 0.0)
+            .flat_map(|(triangle, indices)| {
+                let fallback = Vec3::unit_vector(face_normal(triangle));
+                let direction = |index: usize| match vertex_normals[&index] {
+                    (normal, area) if normal.length() > NORMAL_EPSILON * area => Vec3::unit_vector(normal),
+                    _ => fallback
+                };
+                triangle.displaced(displacement, [direction(indices[0]), direction(indices[1]), direction(indices[2])])
+            })
+            .collect();
+        Ok(Mesh::new(triangles))
+    }
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.bvh.hit(ray, t_min, t_max, hit_record)
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.bvh.bounding_box(time0, time1)
+    }
+}
+
+fn face_normal(triangle: &Triangle) -> Vec3 {
+    let [p0, p1, p2] = triangle.vertices();
+    (p1 - p0).cross(p2 - p0)
+}
+
+fn invalid_data(path: &Path, line: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}:{}: {}", path.display(), line, message),
+    )
+}
+
+fn parse_floats<'a, I>(path: &Path, line: usize, tokens: I, count: usize) -> io::Result<Vec<f32>>
+    where I: Iterator<Item=&'a str> {
+    let values = tokens
+        .take(count)
+        .map(|token| token.parse::<f32>()
+            .map_err(|_| invalid_data(path, line, &format!("invalid number '{}'", token))))
+        .collect::<io::Result<Vec<f32>>>()?;
+    if values.len() < count {
+        return Err(invalid_data(path, line, &format!("expected {} numbers", count)));
+    }
+    Ok(values)
+}
+
+fn resolve_index(path: &Path, line: usize, token: &str, count: usize) -> io::Result<usize> {
+    let index = token.parse::<i64>()
+        .map_err(|_| invalid_data(path, line, &format!("invalid index '{}'", token)))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(invalid_data(path, line, &format!("index {} out of range", index)));
+    }
+    Ok(resolved as usize)
+}
+
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+fn parse_obj(path: &Path, source: &str, default_material: Option<Arc<dyn Material>>) -> io::Result<(Vec<Triangle>, Vec<[usize; 3]>)> {
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<(f32, f32)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut current_material = default_material;
+    let mut triangles = Vec::new();
+    let mut corner_positions = Vec::new();
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = index + 1;
+        let content = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        match keyword {
+            "v" => {
+                let v = parse_floats(path, line, tokens, 3)?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_floats(path, line, tokens, 2)?;
+                uvs.push((v[0], v[1]));
+            }
+            "vn" => {
+                let v = parse_floats(path, line, tokens, 3)?;
+                normals.push(Vec3::new(v[0], v[1], v[2]));
+            }
+            "f" => {
+                let mut face = Vec::new();
+                for token in tokens {
+                    let mut parts = token.split('/');
+                    let position = resolve_index(path, line, parts.next().unwrap_or(""), positions.len())?;
+                    let uv = match parts.next() {
+                        Some(part) if !part.is_empty() => Some(resolve_index(path, line, part, uvs.len())?),
+                        _ => None
+                    };
+                    let normal = match parts.next() {
+                        Some(part) if !part.is_empty() => Some(resolve_index(path, line, part, normals.len())?),
+                        _ => None
+                    };
+                    face.push(FaceVertex { position, uv, normal });
+                }
+                if face.len() < 3 {
+                    return Err(invalid_data(path, line, "a face needs at least three vertices"));
+                }
+                for k in 1..face.len() - 1 {
+                    let corners = [&face[0], &face[k], &face[k + 1]];
+                    let mut triangle = Triangle::new(
+                        positions[corners[0].position],
+                        positions[corners[1].position],
+                        positions[corners[2].position],
+                        current_material.clone(),
+                    );
+                    if let (Some(n0), Some(n1), Some(n2)) = (corners[0].normal, corners[1].normal, corners[2].normal) {
+                        triangle = triangle.with_normals([normals[n0], normals[n1], normals[n2]]);
+                    }
+                    if let (Some(t0), Some(t1), Some(t2)) = (corners[0].uv, corners[1].uv, corners[2].uv) {
+                        triangle = triangle.with_uvs([uvs[t0], uvs[t1], uvs[t2]]);
+                    }
+                    triangles.push(triangle);
+                    corner_positions.push([corners[0].position, corners[1].position, corners[2].position]);
+                }
+            }
+            "mtllib" => {
+                let directory = path.parent().unwrap_or_else(|| Path::new(""));
+                for library in tokens {
+                    materials.extend(load_materials(&directory.join(library))?);
+                }
+            }
+            "usemtl" => {
+                let name = tokens.next()
+                    .ok_or_else(|| invalid_data(path, line, "usemtl without a material name"))?;
+                let material = materials.get(name)
+                    .ok_or_else(|| invalid_data(path, line, &format!("unknown material `{}`", name)))?;
+                current_material = Some(material.clone());
+            }
+            _ => continue
+        }
+    }
+    Ok((triangles, corner_positions))
+}
+
+struct MtlEntry {
+    name: String,
+    diffuse: Color,
+    diffuse_map: Option<PathBuf>,
+    specular: Color,
+    shininess: f32,
+    ref_idx: Option<f32>,
+    dissolve: f32,
+    illum: i32,
+}
+
+impl MtlEntry {
+    fn new(name: &str) -> MtlEntry {
+        MtlEntry {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            diffuse_map: None,
+            specular: Color::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            ref_idx: None,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+    fn to_material(&self) -> io::Result<Arc<dyn Material>> {
+        if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Ok(Arc::new(Dielectric::new(self.ref_idx.unwrap_or(1.5))));
+        }
+        if matches!(self.illum, 3 | 5 | 8) {
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            return Ok(Arc::new(Metal::new(self.specular, fuzz)));
+        }
+        match &self.diffuse_map {
+            Some(path) => Ok(Arc::new(Lambertian::textured(Arc::new(ImageTexture::load(path)?)))),
+            None => Ok(Arc::new(Lambertian::new(self.diffuse)))
+        }
+    }
+}
+
+fn load_materials(path: &Path) -> io::Result<HashMap<String, Arc<dyn Material>>> {
+    let source = fs::read_to_string(path)?;
+    let mut entries: Vec<MtlEntry> = Vec::new();
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = index + 1;
+        let content = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue
+        };
+        if keyword == "newmtl" {
+            let name = tokens.next()
+                .ok_or_else(|| invalid_data(path, line, "newmtl without a material name"))?;
+            entries.push(MtlEntry::new(name));
+            continue;
+        }
+        let entry = match entries.last_mut() {
+            Some(entry) => entry,
+            None => continue
+        };
+        match keyword {
+            "Kd" => {
+                let v = parse_floats(path, line, tokens, 3)?;
+                entry.diffuse = Color::new(v[0], v[1], v[2]);
+            }
+            "Ks" => {
+                let v = parse_floats(path, line, tokens, 3)?;
+                entry.specular = Color::new(v[0], v[1], v[2]);
+            }
+            "map_Kd" => {
+                let file = tokens.last()
+                    .ok_or_else(|| invalid_data(path, line, "map_Kd without a file name"))?;
+                let directory = path.parent().unwrap_or_else(|| Path::new(""));
+                entry.diffuse_map = Some(directory.join(file));
+            }
+            "Ns" => entry.shininess = parse_floats(path, line, tokens, 1)?[0],
+            "Ni" => entry.ref_idx = Some(parse_floats(path, line, tokens, 1)?[0]),
+            "d" => entry.dissolve = parse_floats(path, line, tokens, 1)?[0],
+            "Tr" => entry.dissolve = 1.0 - parse_floats(path, line, tokens, 1)?[0],
+            "illum" => {
+                entry.illum = tokens.next()
+                    .and_then(|token| token.parse::<i32>().ok())
+                    .ok_or_else(|| invalid_data(path, line, "invalid illumination model"))?;
+            }
+            _ => continue
+        }
+    }
+    entries.iter()
+        .map(|entry| Ok((entry.name.clone(), entry.to_material()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use crate::graphics::texture::SolidColor;
+
+    fn write_obj(name: &str, source: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("leonardo-{}-{}.obj", name, std::process::id()));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    fn parse(source: &str) -> io::Result<(Vec<Triangle>, Vec<[usize; 3]>)> {
+        parse_obj(Path::new("test.obj"), source, None)
+    }
+
+    fn hit_from_above(triangle: &Triangle, x: f32, y: f32) -> HitRecord {
+        let ray = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        triangle.hit(ray, 0.001, f32::MAX, &HitRecord::new_def()).unwrap()
+    }
+
+    #[test]
+    fn polygon_faces_are_split_into_a_triangle_fan() {
+        let (triangles, corners) = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 2 0\nf 1 2 3 4 5\n").unwrap();
+        assert_eq!(corners, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+        let [p0, p1, p2] = triangles[1].vertices();
+        assert_eq!((p0.x(), p0.y(), p1.x(), p1.y(), p2.x(), p2.y()), (0.0, 0.0, 1.0, 1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_latest_vertex() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5\nf 1 -3 -2\nf -1 -4 2\n";
+        let (_, corners) = parse(source).unwrap();
+        assert_eq!(corners, vec![[0, 1, 2], [0, 1, 2], [3, 0, 1]]);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_rejected() {
+        for face in &["f 0 1 2", "f 1 2 4", "f 1 2 -4", "f 1/1 2/1 3/1", "f 1 2"] {
+            let err = parse(&format!("v 0 0 0\nv 1 0 0\nv 0 1 0\n{}\n", face)).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with("test.obj:4: "), "{}", err);
+        }
+    }
+
+    #[test]
+    fn face_vertices_carry_uvs_and_normals() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+            vt 0.2 0.2\nvt 0.6 0.2\nvt 0.2 1.0\n\
+            vn 0 0 1\nvn 0 1 0\n\
+            f 1/1/1 2/2/1 3/3/2\nf 1//1 2//1 3//2\nf 1/1 2/2 3/3\n";
+        let (triangles, _) = parse(source).unwrap();
+
+        let full = hit_from_above(&triangles[0], 0.25, 0.5);
+        assert!((full.u - 0.3).abs() < 1e-5 && (full.v - 0.6).abs() < 1e-5, "{} {}", full.u, full.v);
+        let expected = Vec3::unit_vector(Vec3::new(0.0, 1.0, 1.0));
+        assert!((full.shading_normal - expected).length() < 1e-5, "{}", full.shading_normal);
+
+        let normals_only = hit_from_above(&triangles[1], 0.25, 0.5);
+        assert!((normals_only.u - 0.25).abs() < 1e-5 && (normals_only.v - 0.5).abs() < 1e-5);
+        assert!((normals_only.shading_normal - expected).length() < 1e-5, "{}", normals_only.shading_normal);
+
+        let uvs_only = hit_from_above(&triangles[2], 0.25, 0.5);
+        assert!((uvs_only.u - 0.3).abs() < 1e-5 && (uvs_only.v - 0.6).abs() < 1e-5);
+        assert!((uvs_only.shading_normal - uvs_only.normal).length() < 1e-6, "{}", uvs_only.shading_normal);
+    }
+
+    #[test]
+    fn unknown_material_names_are_an_error() {
+        let err = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl missing\nf 1 2 3\n").err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "test.obj:4: unknown material `missing`");
+    }
+
+    #[test]
+    fn cancelling_vertex_normals_fall_back_to_the_face_normal() {
+        let path = write_obj("cancelling", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 3 2\n");
+        let displacement = Displacement {
+            height: Arc::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))),
+            scale: 0.1,
+            subdivisions: 2,
+        };
+        let mesh = Mesh::load_displaced(&path, None, &displacement);
+        fs::remove_file(&path).unwrap();
+        let ray = Ray::new(Point3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = mesh.unwrap().hit(ray, 0.001, f32::MAX, &HitRecord::new_def()).unwrap();
+        assert!((hit.t - 0.9).abs() < 1e-4, "{}", hit.t);
+        assert!(hit.shading_normal.z() > 0.99, "{}", hit.shading_normal);
+    }
+}
@@ -0,0 +1,303 @@
+use std::f32::consts::PI;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Color, Vec3};
+
+const MIN_ALPHA: f32 = 1e-4;
+const SMOOTH_ALPHA: f32 = 1e-3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Ggx {
+    alpha_x: f32,
+    alpha_y: f32,
+}
+
+impl Ggx {
+    pub fn new(roughness: f32, anisotropy: f32) -> Ggx {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        let aspect = (1.0 - 0.9 * anisotropy.clamp(-1.0, 1.0).abs()).sqrt();
+        if anisotropy < 0.0 {
+            return Ggx::from_alpha(alpha * aspect, alpha / aspect);
+        }
+        Ggx::from_alpha(alpha / aspect, alpha * aspect)
+    }
+    pub fn from_alpha(alpha_x: f32, alpha_y: f32) -> Ggx {
+        Ggx {
+            alpha_x: alpha_x.max(MIN_ALPHA),
+            alpha_y: alpha_y.max(MIN_ALPHA),
+        }
+    }
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+    pub fn d(&self, h: Vec3) -> f32 {
+        if h.z() <= 0.0 {
+            return 0.0;
+        }
+        let x = h.x() / self.alpha_x;
+        let y = h.y() / self.alpha_y;
+        let denominator = x * x + y * y + h.z() * h.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * denominator * denominator)
+    }
+    pub fn lambda(&self, w: Vec3) -> f32 {
+        if w.z() == 0.0 {
+            return f32::INFINITY;
+        }
+        let x = self.alpha_x * w.x();
+        let y = self.alpha_y * w.y();
+        0.5 * ((1.0 + (x * x + y * y) / (w.z() * w.z())).sqrt() - 1.0)
+    }
+    pub fn g1(&self, w: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+    pub fn visible_pdf(&self, wo: Vec3, h: Vec3) -> f32 {
+        let cosine = wo.dot(h);
+        if wo.z() <= 0.0 || cosine <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * cosine * self.d(h) / wo.z()
+    }
+    pub fn sample_visible(&self, wo: Vec3) -> Vec3 {
+        let vh = Vec3::unit_vector(Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()));
+        let length_square = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if length_square > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / length_square.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(t1);
+        let r = random_f32().sqrt();
+        let phi = 2.0 * PI * random_f32();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        Vec3::unit_vector(Vec3::new(self.alpha_x * nh.x(), self.alpha_y * nh.y(), nh.z().max(1e-6)))
+    }
+    pub fn sample_reflection(&self, wo: Vec3) -> Option<Vec3> {
+        let wi = mirror(wo, self.sample_visible(wo));
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        Some(wi)
+    }
+    pub fn reflection(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = Vec3::unit_vector(wo + wi);
+        self.d(h) * self.g(wo, wi) / (4.0 * wo.z())
+    }
+    pub fn reflection_pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = Vec3::unit_vector(wo + wi);
+        self.visible_pdf(wo, h) / (4.0 * wo.dot(h))
+    }
+    pub fn sample_dielectric(&self, wo: Vec3, eta: f32) -> Option<Vec3> {
+        let h = self.sample_visible(wo);
+        let cosine = wo.dot(h);
+        let wi = if random_f32() < fresnel_dielectric(cosine, eta) {
+            mirror(wo, h)
+        } else {
+            refract(wo, h, eta)
+        };
+        if wi.z() == 0.0 || (wi.z() > 0.0) != (wi.dot(h) > 0.0) {
+            return None;
+        }
+        Some(wi)
+    }
+    pub fn dielectric(&self, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+        let h = match dielectric_half_vector(wo, wi, eta) {
+            Some(h) => h,
+            None => return 0.0
+        };
+        let fresnel = fresnel_dielectric(wo.dot(h), eta);
+        let dg = self.d(h) * self.g(wo, wi);
+        if wi.z() > 0.0 {
+            return fresnel * dg / (4.0 * wo.z());
+        }
+        let denominator = wi.dot(h) + wo.dot(h) / eta;
+        (1.0 - fresnel) * dg * (wi.dot(h) * wo.dot(h)).abs() / (wo.z() * denominator * denominator)
+    }
+    pub fn dielectric_pdf(&self, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+        let h = match dielectric_half_vector(wo, wi, eta) {
+            Some(h) => h,
+            None => return 0.0
+        };
+        let fresnel = fresnel_dielectric(wo.dot(h), eta);
+        let visible = self.visible_pdf(wo, h);
+        if wi.z() > 0.0 {
+            return fresnel * visible / (4.0 * wo.dot(h));
+        }
+        let denominator = wi.dot(h) + wo.dot(h) / eta;
+        (1.0 - fresnel) * visible * wi.dot(h).abs() / (denominator * denominator)
+    }
+}
+
+pub fn mirror(wo: Vec3, h: Vec3) -> Vec3 {
+    h * (2.0 * wo.dot(h)) - wo
+}
+
+pub fn refract(wo: Vec3, h: Vec3, eta: f32) -> Vec3 {
+    let cosine = wo.dot(h);
+    let sin_square_t = (1.0 - cosine * cosine).max(0.0) / (eta * eta);
+    let cos_t = (1.0 - sin_square_t).max(0.0).sqrt();
+    -wo / eta + h * (cosine / eta - cos_t)
+}
+
+fn dielectric_half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+    if wo.z() <= 0.0 || wi.z() == 0.0 {
+        return None;
+    }
+    let h = if wi.z() > 0.0 { wo + wi } else { wo + wi * eta };
+    if h.length_square() == 0.0 {
+        return None;
+    }
+    let h = Vec3::unit_vector(h);
+    let h = if h.z() < 0.0 { -h } else { h };
+    if wo.dot(h) <= 0.0 || (wi.z() > 0.0) != (wi.dot(h) > 0.0) {
+        return None;
+    }
+    Some(h)
+}
+
+pub fn fresnel_schlick(cos_theta: f32, f0: Color) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Color::new(1.0, 1.0, 1.0) - f0) * weight
+}
+
+pub fn fresnel_dielectric(cos_theta: f32, eta: f32) -> f32 {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    let sin_square_t = (1.0 - cos_theta * cos_theta) / (eta * eta);
+    if sin_square_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin_square_t).sqrt();
+    let parallel = (eta * cos_theta - cos_t) / (eta * cos_theta + cos_t);
+    let perpendicular = (cos_theta - eta * cos_t) / (cos_theta + eta * cos_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+fn fresnel_conductor_channel(cos_theta: f32, eta: f32, k: f32) -> f32 {
+    let cos_square = cos_theta * cos_theta;
+    let sin_square = 1.0 - cos_square;
+    let eta_square = eta * eta;
+    let k_square = k * k;
+    let t0 = eta_square - k_square - sin_square;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta_square * k_square).sqrt();
+    let t1 = a2_plus_b2 + cos_square;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos_square * a2_plus_b2 + sin_square * sin_square;
+    let t4 = t2 * sin_square;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rs + rp)
+}
+
+pub fn fresnel_conductor(cos_theta: f32, eta: Color, k: Color) -> Color {
+    let cos_theta = cos_theta.clamp(0.0, 1.0);
+    Color::new(
+        fresnel_conductor_channel(cos_theta, eta.x(), k.x()),
+        fresnel_conductor_channel(cos_theta, eta.y(), k.y()),
+        fresnel_conductor_channel(cos_theta, eta.z(), k.z()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+
+    const SAMPLES: usize = 100_000;
+
+    fn distributions() -> [Ggx; 3] {
+        [Ggx::new(0.6, 0.0), Ggx::new(0.8, 0.0), Ggx::new(0.7, 0.8)]
+    }
+
+    fn outgoing(cos_theta: f32) -> Vec3 {
+        Vec3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, cos_theta)
+    }
+
+    fn sphere_integral(f: impl Fn(Vec3) -> f32) -> f32 {
+        const STEPS: usize = 500;
+        let mut sum = 0.0;
+        for i in 0..STEPS {
+            let z = 1.0 - 2.0 * (i as f32 + 0.5) / STEPS as f32;
+            let r = (1.0 - z * z).sqrt();
+            for j in 0..2 * STEPS {
+                let phi = PI * (j as f32 + 0.5) / STEPS as f32;
+                sum += f(Vec3::new(r * phi.cos(), r * phi.sin(), z));
+            }
+        }
+        sum * 2.0 * PI / (STEPS * STEPS) as f32
+    }
+
+    fn acceptance(sample: impl Fn() -> Option<Vec3>) -> f32 {
+        (0..SAMPLES).filter(|_| sample().is_some()).count() as f32 / SAMPLES as f32
+    }
+
+    #[test]
+    fn projected_microfacet_area_is_one() {
+        seed_rng(5);
+        for ggx in distributions().iter() {
+            let area = sphere_integral(|h| ggx.d(h) * h.z().max(0.0));
+            assert!((area - 1.0).abs() < 0.005, "{:?}: {}", ggx, area);
+        }
+    }
+
+    #[test]
+    fn sampled_reflection_matches_its_pdf() {
+        seed_rng(6);
+        for ggx in distributions().iter() {
+            for &cos_theta in &[0.9, 0.5] {
+                let wo = outgoing(cos_theta);
+                let integral = sphere_integral(|wi| ggx.reflection_pdf(wo, wi));
+                let accepted = acceptance(|| ggx.sample_reflection(wo));
+                assert!((integral - accepted).abs() < 0.01, "{:?} at {}: {} vs {}", ggx, cos_theta, integral, accepted);
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_dielectric_matches_its_pdf() {
+        seed_rng(8);
+        for ggx in distributions().iter() {
+            for &eta in &[1.5, 1.0 / 1.5] {
+                let wo = outgoing(0.7);
+                let integral = sphere_integral(|wi| ggx.dielectric_pdf(wo, wi, eta));
+                let accepted = acceptance(|| ggx.sample_dielectric(wo, eta));
+                assert!((integral - accepted).abs() < 0.01, "{:?} with eta {}: {} vs {}", ggx, eta, integral, accepted);
+            }
+        }
+    }
+
+    #[test]
+    fn white_furnace_only_loses_energy_to_masking() {
+        seed_rng(9);
+        let ggx = Ggx::new(0.3, 0.0);
+        let wo = outgoing(0.8);
+        let mut reflected = 0.0;
+        let mut scattered = 0.0;
+        for _ in 0..SAMPLES {
+            if let Some(wi) = ggx.sample_reflection(wo) {
+                let weight = ggx.reflection(wo, wi) / ggx.reflection_pdf(wo, wi);
+                assert!(weight <= 1.0 + 1e-4, "{}", weight);
+                reflected += weight;
+            }
+            if let Some(wi) = ggx.sample_dielectric(wo, 1.5) {
+                let weight = ggx.dielectric(wo, wi, 1.5) / ggx.dielectric_pdf(wo, wi, 1.5);
+                assert!(weight <= 1.0 + 1e-4, "{}", weight);
+                scattered += weight;
+            }
+        }
+        for &albedo in &[reflected / SAMPLES as f32, scattered / SAMPLES as f32] {
+            assert!((0.95..=1.0).contains(&albedo), "{}", albedo);
+        }
+    }
+}
@@ -1,6 +1,37 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
 pub mod camera;
+pub mod cone;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
+pub mod environment;
+pub mod exr;
+pub mod hdr;
 pub mod hit;
+pub mod image;
+pub mod light;
+pub mod medium;
+pub mod mesh;
+pub mod microfacet;
+pub mod moving_sphere;
+pub mod plane;
+pub mod png;
+pub mod principled;
 pub mod ppm;
+pub mod quad;
+pub mod quadric;
 pub mod ray;
+pub mod rect;
+pub mod shading;
+pub mod sky;
+pub mod spectrum;
 pub mod sphere;
+pub mod texture;
+pub mod tonemap;
+pub mod torus;
+pub mod transformed;
+pub mod triangle;
+pub mod volume;
 pub mod material;
\ No newline at end of file
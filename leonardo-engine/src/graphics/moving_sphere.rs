@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+use crate::graphics::sphere::hit_sphere;
+
+pub struct MovingSphere {
+    keyframes: Vec<(f32, Point3)>,
+    radius: f32,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl MovingSphere {
+    pub fn linear(center0: Point3, center1: Point3, time0: f32, time1: f32, radius: f32, material: Option<Arc<dyn Material>>) -> MovingSphere {
+        MovingSphere::keyframed(vec![(time0, center0), (time1, center1)], radius, material)
+    }
+    pub fn keyframed(mut keyframes: Vec<(f32, Point3)>, radius: f32, material: Option<Arc<dyn Material>>) -> MovingSphere {
+        assert!(!keyframes.is_empty(), "a moving sphere needs at least one keyframe");
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        MovingSphere {
+            keyframes,
+            radius,
+            material,
+        }
+    }
+    pub fn center(&self, time: f32) -> Point3 {
+        let index = self.keyframes.partition_point(|&(key_time, _)| key_time <= time);
+        if index == 0 {
+            return self.keyframes[0].1;
+        }
+        if index == self.keyframes.len() {
+            return self.keyframes[index - 1].1;
+        }
+        let (time0, center0) = self.keyframes[index - 1];
+        let (time1, center1) = self.keyframes[index];
+        let t = (time - time0) / (time1 - time0);
+        center0 + (center1 - center0) * t
+    }
+    fn sphere_box(&self, center: Point3) -> Aabb {
+        let r = self.radius.abs();
+        let extent = Vec3::new(r, r, r);
+        Aabb::new(center - extent, center + extent)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        hit_sphere(self.center(ray.time()), self.radius, &self.material, ray, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let output_box = Aabb::surrounding(self.sphere_box(self.center(time0)), self.sphere_box(self.center(time1)));
+        Some(self.keyframes.iter()
+            .filter(|&&(time, _)| time > time0 && time < time1)
+            .fold(output_box, |acc, &(_, center)| Aabb::surrounding(acc, self.sphere_box(center))))
+    }
+}
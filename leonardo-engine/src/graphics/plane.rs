@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::algebra::onb::Onb;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+const EPSILON: f32 = 1e-8;
+
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    basis: Onb,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Option<Arc<dyn Material>>) -> Plane {
+        let normal = Vec3::unit_vector(normal);
+        Plane {
+            point,
+            normal,
+            basis: Onb::new(normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+        let t = (self.point - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let offset = point - self.point;
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = point;
+        hit_record.u = offset.dot(self.basis.u());
+        hit_record.v = offset.dot(self.basis.v());
+        hit_record.dpdu = self.basis.u();
+        hit_record.dpdv = self.basis.v();
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.normal))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        None
+    }
+}
@@ -0,0 +1,89 @@
+use std::io::{self, Write};
+
+use crate::graphics::image::{quantize_16, quantize_8, Framebuffer, ImageEncoder};
+use crate::graphics::tonemap::DisplayTransform;
+
+pub struct PngEncoder {
+    bit_depth: u8,
+    transform: DisplayTransform,
+}
+
+impl PngEncoder {
+    pub fn new(bit_depth: u8, transform: DisplayTransform) -> PngEncoder {
+        PngEncoder {
+            bit_depth: if bit_depth > 8 { 16 } else { 8 },
+            transform,
+        }
+    }
+}
+
+fn to_io_error(err: png::EncodingError) -> io::Error {
+    io::Error::other(err)
+}
+
+impl ImageEncoder for PngEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, framebuffer.width() as u32, framebuffer.height() as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut data = Vec::new();
+        if self.bit_depth == 16 {
+            encoder.set_depth(png::BitDepth::Sixteen);
+            for color in framebuffer.pixels() {
+                let color = self.transform.apply(*color);
+                for channel in [color.x(), color.y(), color.z()].iter() {
+                    data.extend_from_slice(&quantize_16(*channel).to_be_bytes());
+                }
+            }
+        } else {
+            encoder.set_depth(png::BitDepth::Eight);
+            for color in framebuffer.pixels() {
+                let color = self.transform.apply(*color);
+                data.extend_from_slice(&[quantize_8(color.x()), quantize_8(color.y()), quantize_8(color.z())]);
+            }
+        }
+        let mut png_writer = encoder.write_header().map_err(to_io_error)?;
+        png_writer.write_image_data(&data).map_err(to_io_error)?;
+        png_writer.finish().map_err(to_io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::vec3::Color;
+    use crate::graphics::tonemap::{ToneMapOperator, TransferFunction};
+
+    fn round_trip(bit_depth: u8) -> (png::OutputInfo, Vec<u8>) {
+        let mut framebuffer = Framebuffer::new(2, 2);
+        framebuffer.set(0, 0, Color::new(0.0, 0.5, 1.0));
+        framebuffer.set(1, 0, Color::new(2.0, -1.0, 0.25));
+        framebuffer.set(0, 1, Color::new(0.25, 0.25, 0.25));
+        framebuffer.set(1, 1, Color::new(1.0, 0.0, 0.5));
+        let transform = DisplayTransform::new(0.0, ToneMapOperator::Clamp, TransferFunction::Linear);
+        let mut bytes = Vec::new();
+        PngEncoder::new(bit_depth, transform).encode(&framebuffer, &mut bytes).unwrap();
+
+        let mut reader = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        data.truncate(info.buffer_size());
+        (info, data)
+    }
+
+    #[test]
+    fn eight_bit_png_decodes_to_the_quantized_pixels() {
+        let (info, data) = round_trip(8);
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Rgb, png::BitDepth::Eight));
+        assert_eq!(data, vec![0, 128, 255, 255, 0, 64, 64, 64, 64, 255, 0, 128]);
+    }
+
+    #[test]
+    fn sixteen_bit_png_decodes_to_the_quantized_pixels() {
+        let (info, data) = round_trip(16);
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Rgb, png::BitDepth::Sixteen));
+        let channels = data.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect::<Vec<_>>();
+        assert_eq!(channels, vec![0, 32768, 65535, 65535, 0, 16384, 16384, 16384, 16384, 65535, 0, 32768]);
+    }
+}
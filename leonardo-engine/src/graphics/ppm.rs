@@ -1,20 +1,91 @@
+use std::io::{self, Write};
+
 use crate::algebra::vec3::Color;
+use crate::graphics::image::{quantize_8, Framebuffer, ImageEncoder};
+use crate::graphics::tonemap::DisplayTransform;
+
+pub fn write_color(color: Color) -> String {
+    let r = quantize_8(color.x());
+    let g = quantize_8(color.y());
+    let b = quantize_8(color.z());
+    format!("{} {} {}\n", r, g, b)
+}
 
-fn clamp(x: f32, min: f32, max: f32) -> f32 {
-    if x < min {
-        return min;
+pub struct PpmAsciiEncoder {
+    transform: DisplayTransform,
+}
+
+impl PpmAsciiEncoder {
+    pub fn new(transform: DisplayTransform) -> PpmAsciiEncoder {
+        PpmAsciiEncoder {
+            transform
+        }
     }
-    if x > max {
-        return max;
+}
+
+impl ImageEncoder for PpmAsciiEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "P3\n{} {}\n255\n", framebuffer.width(), framebuffer.height())?;
+        for color in framebuffer.pixels() {
+            writer.write_all(write_color(self.transform.apply(*color)).as_bytes())?;
+        }
+        Ok(())
     }
-    return x;
 }
 
+pub struct PpmBinaryEncoder {
+    transform: DisplayTransform,
+}
 
-pub fn write_color(color: Color, samples_per_pixel: f32) -> String {
-    let scale = 1.0 / samples_per_pixel;
-    let r = (clamp((color.x() * scale).sqrt(), 0.0, 0.999) * 256.0) as u32;
-    let g = (clamp((color.y() * scale).sqrt(), 0.0, 0.999) * 256.0) as u32;
-    let b = (clamp((color.z() * scale).sqrt(), 0.0, 0.999) * 256.0) as u32;
-    format!("{} {} {}\n", r, g, b)
-}
\ No newline at end of file
+impl PpmBinaryEncoder {
+    pub fn new(transform: DisplayTransform) -> PpmBinaryEncoder {
+        PpmBinaryEncoder {
+            transform
+        }
+    }
+}
+
+impl ImageEncoder for PpmBinaryEncoder {
+    fn encode(&self, framebuffer: &Framebuffer, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", framebuffer.width(), framebuffer.height())?;
+        let mut data = Vec::with_capacity(framebuffer.pixels().len() * 3);
+        for color in framebuffer.pixels() {
+            let color = self.transform.apply(*color);
+            data.extend_from_slice(&[quantize_8(color.x()), quantize_8(color.y()), quantize_8(color.z())]);
+        }
+        writer.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::tonemap::{ToneMapOperator, TransferFunction};
+
+    fn encode(encoder: &dyn ImageEncoder) -> Vec<u8> {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.set(0, 0, Color::new(0.0, 0.5, 1.0));
+        framebuffer.set(1, 0, Color::new(2.0, -1.0, 0.25));
+        let mut bytes = Vec::new();
+        encoder.encode(&framebuffer, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn linear() -> DisplayTransform {
+        DisplayTransform::new(0.0, ToneMapOperator::Clamp, TransferFunction::Linear)
+    }
+
+    #[test]
+    fn ascii_encoder_writes_a_p3_header_and_decimal_triplets() {
+        let bytes = encode(&PpmAsciiEncoder::new(linear()));
+        assert_eq!(String::from_utf8(bytes).unwrap(), "P3\n2 1\n255\n0 128 255\n255 0 64\n");
+    }
+
+    #[test]
+    fn binary_encoder_writes_a_p6_header_and_raw_bytes() {
+        let bytes = encode(&PpmBinaryEncoder::new(linear()));
+        let header = b"P6\n2 1\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[0, 128, 255, 255, 0, 64]);
+    }
+}
@@ -0,0 +1,393 @@
+use std::f32::consts::PI;
+use std::sync::{Arc, OnceLock};
+
+use crate::algebra::onb::Onb;
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Color, Vec3};
+use crate::graphics::hit::HitRecord;
+use crate::graphics::material::{Material, Scatter};
+use crate::graphics::microfacet::{fresnel_schlick, Ggx};
+use crate::graphics::ray::Ray;
+use crate::graphics::texture::{SolidColor, Texture};
+
+const MIN_ROUGHNESS: f32 = 0.05;
+const COAT_F0: f32 = 0.04;
+const SHEEN_TABLE_SIZE: usize = 32;
+const SHEEN_QUADRATURE: usize = 64;
+
+fn constant(value: f32) -> Arc<dyn Texture> {
+    Arc::new(SolidColor::new(Color::new(value, value, value)))
+}
+
+fn schlick(cos_theta: f32, f0: f32) -> f32 {
+    f0 + (1.0 - f0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+fn sheen_lobe(wo: Vec3, wi: Vec3) -> f32 {
+    if wi.z() <= 0.0 {
+        return 0.0;
+    }
+    let h = Vec3::unit_vector(wo + wi);
+    (1.0 - wi.dot(h).clamp(0.0, 1.0)).powi(5) * wi.z() / PI
+}
+
+fn sheen_albedo(cos_theta: f32) -> f32 {
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        (0..SHEEN_TABLE_SIZE)
+            .map(|i| {
+                let cos_o = (i as f32 / (SHEEN_TABLE_SIZE - 1) as f32).max(1e-3);
+                let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+                let mut sum = 0.0;
+                for j in 0..SHEEN_QUADRATURE {
+                    for k in 0..2 * SHEEN_QUADRATURE {
+                        let cos_i = (j as f32 + 0.5) / SHEEN_QUADRATURE as f32;
+                        let phi = PI * (k as f32 + 0.5) / SHEEN_QUADRATURE as f32;
+                        let sin_i = (1.0 - cos_i * cos_i).sqrt();
+                        sum += sheen_lobe(wo, Vec3::new(sin_i * phi.cos(), sin_i * phi.sin(), cos_i));
+                    }
+                }
+                sum * 2.0 * PI / (2 * SHEEN_QUADRATURE * SHEEN_QUADRATURE) as f32
+            })
+            .collect()
+    });
+    let position = cos_theta.clamp(0.0, 1.0) * (SHEEN_TABLE_SIZE - 1) as f32;
+    let index = (position as usize).min(SHEEN_TABLE_SIZE - 2);
+    let weight = position - index as f32;
+    table[index] * (1.0 - weight) + table[index + 1] * weight
+}
+
+pub struct Principled {
+    base_color: Arc<dyn Texture>,
+    metallic: Arc<dyn Texture>,
+    roughness: Arc<dyn Texture>,
+    specular: Arc<dyn Texture>,
+    sheen: Arc<dyn Texture>,
+    clearcoat: Arc<dyn Texture>,
+    clearcoat_roughness: Arc<dyn Texture>,
+    transmission: Arc<dyn Texture>,
+    emission: Option<Arc<dyn Texture>>,
+    ior: f32,
+    anisotropy: f32,
+}
+
+struct Lobes {
+    base_color: Color,
+    sheen: f32,
+    eta: f32,
+    base: Ggx,
+    coat: Ggx,
+    coat_weight: f32,
+    metal_weight: f32,
+    glass_weight: f32,
+    specular_weight: f32,
+    diffuse_weight: f32,
+}
+
+impl Lobes {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        let mut value = Color::new(0.0, 0.0, 0.0);
+        if wi.z() > 0.0 {
+            let reflection = self.base.reflection(wo, wi);
+            let h = Vec3::unit_vector(wo + wi);
+            value = value
+                + Color::new(1.0, 1.0, 1.0) * (self.coat_weight * self.coat.reflection(wo, wi) + self.specular_weight * reflection)
+                + fresnel_schlick(wo.dot(h), self.base_color) * (self.metal_weight * reflection)
+                + self.base_color * (self.diffuse_weight
+                    * ((1.0 - self.sheen * sheen_albedo(wo.z())) * wi.z() / PI + self.sheen * sheen_lobe(wo, wi)));
+        }
+        if self.glass_weight > 0.0 {
+            let tint = if wi.z() < 0.0 { self.base_color } else { Color::new(1.0, 1.0, 1.0) };
+            value = value + tint * (self.glass_weight * self.base.dielectric(wo, wi, self.eta));
+        }
+        value
+    }
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> f32 {
+        let mut pdf = 0.0;
+        if wi.z() > 0.0 {
+            pdf += self.coat_weight * self.coat.reflection_pdf(wo, wi)
+                + (self.metal_weight + self.specular_weight) * self.base.reflection_pdf(wo, wi)
+                + self.diffuse_weight * wi.z() / PI;
+        }
+        if self.glass_weight > 0.0 {
+            pdf += self.glass_weight * self.base.dielectric_pdf(wo, wi, self.eta);
+        }
+        pdf
+    }
+    fn sample(&self, wo: Vec3) -> Option<Vec3> {
+        let mut xi = random_f32();
+        if xi < self.coat_weight {
+            return self.coat.sample_reflection(wo);
+        }
+        xi -= self.coat_weight;
+        if xi < self.metal_weight + self.specular_weight {
+            return self.base.sample_reflection(wo);
+        }
+        xi -= self.metal_weight + self.specular_weight;
+        if xi < self.glass_weight {
+            return self.base.sample_dielectric(wo, self.eta);
+        }
+        let r = random_f32().sqrt();
+        let phi = 2.0 * PI * random_f32();
+        Some(Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt()))
+    }
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Principled {
+        Principled::textured(Arc::new(SolidColor::new(base_color)))
+    }
+    pub fn textured(base_color: Arc<dyn Texture>) -> Principled {
+        Principled {
+            base_color,
+            metallic: constant(0.0),
+            roughness: constant(0.5),
+            specular: constant(0.5),
+            sheen: constant(0.0),
+            clearcoat: constant(0.0),
+            clearcoat_roughness: constant(0.1),
+            transmission: constant(0.0),
+            emission: None,
+            ior: 1.5,
+            anisotropy: 0.0,
+        }
+    }
+    pub fn with_metallic(mut self, metallic: Arc<dyn Texture>) -> Principled {
+        self.metallic = metallic;
+        self
+    }
+    pub fn with_roughness(mut self, roughness: Arc<dyn Texture>) -> Principled {
+        self.roughness = roughness;
+        self
+    }
+    pub fn with_specular(mut self, specular: Arc<dyn Texture>) -> Principled {
+        self.specular = specular;
+        self
+    }
+    pub fn with_sheen(mut self, sheen: Arc<dyn Texture>) -> Principled {
+        self.sheen = sheen;
+        self
+    }
+    pub fn with_clearcoat(mut self, clearcoat: Arc<dyn Texture>) -> Principled {
+        self.clearcoat = clearcoat;
+        self
+    }
+    pub fn with_clearcoat_roughness(mut self, clearcoat_roughness: Arc<dyn Texture>) -> Principled {
+        self.clearcoat_roughness = clearcoat_roughness;
+        self
+    }
+    pub fn with_transmission(mut self, transmission: Arc<dyn Texture>) -> Principled {
+        self.transmission = transmission;
+        self
+    }
+    pub fn with_emission(mut self, emission: Arc<dyn Texture>) -> Principled {
+        self.emission = Some(emission);
+        self
+    }
+    pub fn with_ior(mut self, ior: f32) -> Principled {
+        self.ior = ior;
+        self
+    }
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Principled {
+        self.anisotropy = anisotropy;
+        self
+    }
+    fn scalar(texture: &Arc<dyn Texture>, hit_record: &HitRecord) -> f32 {
+        let value = texture.value(hit_record.u, hit_record.v, hit_record.point);
+        ((value.x() + value.y() + value.z()) / 3.0).clamp(0.0, 1.0)
+    }
+    fn lobes(&self, hit_record: &HitRecord, wo: Vec3) -> Lobes {
+        let base_color = self.base_color.value(hit_record.u, hit_record.v, hit_record.point);
+        let base_color = Color::new(base_color.x().clamp(0.0, 1.0), base_color.y().clamp(0.0, 1.0), base_color.z().clamp(0.0, 1.0));
+        let metallic = Principled::scalar(&self.metallic, hit_record);
+        let roughness = Principled::scalar(&self.roughness, hit_record).max(MIN_ROUGHNESS);
+        let specular = Principled::scalar(&self.specular, hit_record);
+        let clearcoat = Principled::scalar(&self.clearcoat, hit_record);
+        let clearcoat_roughness = Principled::scalar(&self.clearcoat_roughness, hit_record).max(MIN_ROUGHNESS);
+        let transmission = Principled::scalar(&self.transmission, hit_record);
+        let coat_weight = clearcoat * schlick(wo.z(), COAT_F0);
+        let dielectric = (1.0 - coat_weight) * (1.0 - metallic);
+        let opaque = dielectric * (1.0 - transmission);
+        let specular_weight = opaque * schlick(wo.z(), 0.08 * specular);
+        Lobes {
+            base_color,
+            sheen: Principled::scalar(&self.sheen, hit_record),
+            eta: if hit_record.front_face { self.ior } else { 1.0 / self.ior },
+            base: Ggx::new(roughness, self.anisotropy),
+            coat: Ggx::new(clearcoat_roughness, 0.0),
+            coat_weight,
+            metal_weight: (1.0 - coat_weight) * metallic,
+            glass_weight: dielectric * transmission,
+            specular_weight,
+            diffuse_weight: opaque - specular_weight,
+        }
+    }
+    fn local(&self, ray: Ray, hit_record: &HitRecord) -> (Onb, Vec3) {
+        let frame = hit_record.shading_frame();
+        (frame, frame.coordinates(-Vec3::unit_vector(ray.direction())))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: Ray, hit_record: &HitRecord, _attenuation: Color, _scattered: Ray) -> Scatter {
+        let (frame, wo) = self.local(ray, hit_record);
+        let absorbed = Scatter {
+            attenuation: Color::new(0.0, 0.0, 0.0),
+            scattered: ray,
+            is_scattered: false,
+            is_specular: false,
+            pdf: 0.0,
+        };
+        if wo.z() <= 0.0 {
+            return absorbed;
+        }
+        let lobes = self.lobes(hit_record, wo);
+        let wi = match lobes.sample(wo) {
+            Some(wi) => wi,
+            None => return absorbed
+        };
+        let pdf = lobes.pdf(wo, wi);
+        if pdf <= 0.0 {
+            return absorbed;
+        }
+        Scatter {
+            attenuation: lobes.eval(wo, wi) / pdf,
+            scattered: Ray::with_time(hit_record.point, frame.local(wi), ray.time()),
+            is_scattered: true,
+            is_specular: false,
+            pdf,
+        }
+    }
+    fn emitted(&self, _ray: Ray, hit_record: &HitRecord) -> Color {
+        match &self.emission {
+            Some(emission) if hit_record.front_face => emission.value(hit_record.u, hit_record.v, hit_record.point),
+            _ => Color::new(0.0, 0.0, 0.0)
+        }
+    }
+    fn is_emissive(&self) -> bool {
+        self.emission.is_some()
+    }
+    fn eval(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+        let (frame, wo) = self.local(ray, hit_record);
+        if wo.z() <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.lobes(hit_record, wo).eval(wo, frame.coordinates(Vec3::unit_vector(direction)))
+    }
+    fn pdf(&self, ray: Ray, hit_record: &HitRecord, direction: Vec3) -> f32 {
+        let (frame, wo) = self.local(ray, hit_record);
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+        self.lobes(hit_record, wo).pdf(wo, frame.coordinates(Vec3::unit_vector(direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+    use crate::algebra::vec3::Point3;
+
+    const SAMPLES: usize = 50_000;
+
+    fn furnace_albedo(material: &Principled, cos_theta: f32) -> Color {
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(sin_theta, 0.0, -cos_theta));
+        let hit_record = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            0.5,
+            0.5,
+            true,
+            None,
+        );
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..SAMPLES {
+            let scatter = material.sample(ray, &hit_record);
+            if scatter.is_scattered {
+                sum = sum + scatter.attenuation;
+            }
+        }
+        sum / SAMPLES as f32
+    }
+
+    fn white() -> Principled {
+        Principled::new(Color::new(1.0, 1.0, 1.0))
+    }
+
+    fn configurations() -> Vec<(&'static str, Principled)> {
+        vec![
+            ("diffuse", white()),
+            ("rough diffuse", white().with_roughness(constant(1.0))),
+            ("metal", white().with_metallic(constant(1.0)).with_roughness(constant(0.2))),
+            ("specular", white().with_specular(constant(1.0)).with_roughness(constant(0.3))),
+            ("sheen", white().with_sheen(constant(1.0))),
+            ("clearcoat", white().with_clearcoat(constant(1.0)).with_clearcoat_roughness(constant(0.05))),
+            ("glass", white().with_transmission(constant(1.0)).with_roughness(constant(0.1))),
+            (
+                "everything",
+                white()
+                    .with_metallic(constant(0.3))
+                    .with_sheen(constant(0.5))
+                    .with_clearcoat(constant(0.5))
+                    .with_transmission(constant(0.3))
+                    .with_anisotropy(0.5),
+            ),
+        ]
+    }
+
+    #[test]
+    fn white_furnace_does_not_create_energy() {
+        seed_rng(7);
+        for (name, material) in configurations() {
+            for cos_theta in [1.0, 0.7, 0.3, 0.05] {
+                let albedo = furnace_albedo(&material, cos_theta);
+                for channel in 0..3 {
+                    assert!(albedo[channel] <= 1.01, "{} reflects {} at cos {}", name, albedo, cos_theta);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn white_furnace_keeps_smooth_lobes_nearly_lossless() {
+        seed_rng(11);
+        let materials = [
+            ("diffuse", white()),
+            ("metal", white().with_metallic(constant(1.0)).with_roughness(constant(0.2))),
+            ("sheen", white().with_sheen(constant(1.0))),
+            ("glass", white().with_transmission(constant(1.0)).with_roughness(constant(0.1))),
+        ];
+        for (name, material) in materials {
+            let albedo = furnace_albedo(&material, 0.8);
+            for channel in 0..3 {
+                assert!(albedo[channel] >= 0.95, "{} only reflects {}", name, albedo);
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_weights_match_eval_over_pdf() {
+        seed_rng(3);
+        let material = white()
+            .with_metallic(constant(0.4))
+            .with_sheen(constant(0.5))
+            .with_clearcoat(constant(0.7))
+            .with_transmission(constant(0.2));
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.6, 0.0, -0.8));
+        let hit_record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, 0.0, 0.0, true, None);
+        for _ in 0..1000 {
+            let scatter = material.sample(ray, &hit_record);
+            if !scatter.is_scattered {
+                continue;
+            }
+            let direction = scatter.scattered.direction();
+            let pdf = material.pdf(ray, &hit_record, direction);
+            assert!((pdf - scatter.pdf).abs() <= 1e-3 * pdf.max(1.0));
+            let expected = material.eval(ray, &hit_record, direction) / pdf;
+            assert!((expected - scatter.attenuation).length() <= 1e-3 * expected.length().max(1.0));
+        }
+    }
+}
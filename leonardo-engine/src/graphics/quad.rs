@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+const EPSILON: f32 = 1e-8;
+const BOX_PADDING: f32 = 1e-4;
+
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    w: Vec3,
+    area: f32,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: Option<Arc<dyn Material>>) -> Quad {
+        let n = u.cross(v);
+        Quad {
+            corner,
+            u,
+            v,
+            normal: Vec3::unit_vector(n),
+            w: n / n.length_square(),
+            area: n.length(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+        let t = (self.corner - ray.origin()).dot(self.normal) / denominator;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let planar = point - self.corner;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = point;
+        hit_record.u = alpha;
+        hit_record.v = beta;
+        hit_record.dpdu = self.u;
+        hit_record.dpdv = self.v;
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.normal))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let corners = [self.corner + self.u, self.corner + self.v, self.corner + self.u + self.v];
+        let padding = Vec3::new(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+        let (minimum, maximum) = corners.iter()
+            .fold((self.corner, self.corner), |(min, max), &p| (Vec3::min(min, p), Vec3::max(max, p)));
+        Some(Aabb::new(minimum - padding, maximum + padding))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let hit_record = match self.hit(Ray::new(origin, direction), 0.001, f32::MAX, &HitRecord::new_def()) {
+            Some(hit_record) => hit_record,
+            None => return 0.0
+        };
+        let distance_squared = hit_record.t * hit_record.t * direction.length_square();
+        let cosine = self.normal.dot(direction).abs() / direction.length();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        let point = self.corner + self.u * random_f32() + self.v * random_f32();
+        point - origin
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algebra::roots::solve_quadratic;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+use crate::graphics::sphere::sphere_uv;
+use crate::scene::{is_zero, serialize_f32};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuadricCoefficients {
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub xx: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub yy: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub zz: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub xy: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub yz: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub xz: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub x: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub y: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub z: f32,
+    #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub constant: f32,
+}
+
+pub struct Quadric {
+    q: QuadricCoefficients,
+    bounds: Aabb,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Quadric {
+    pub fn new(coefficients: QuadricCoefficients, bounds: Aabb, material: Option<Arc<dyn Material>>) -> Quadric {
+        Quadric {
+            q: coefficients,
+            bounds,
+            material,
+        }
+    }
+    fn gradient(&self, p: Point3) -> Vec3 {
+        let q = &self.q;
+        Vec3::new(
+            2.0 * q.xx * p.x() + q.xy * p.y() + q.xz * p.z() + q.x,
+            2.0 * q.yy * p.y() + q.xy * p.x() + q.yz * p.z() + q.y,
+            2.0 * q.zz * p.z() + q.yz * p.y() + q.xz * p.x() + q.z,
+        )
+    }
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let q = &self.q;
+        let (o, d) = (ray.origin(), ray.direction());
+        let a = q.xx * d.x() * d.x() + q.yy * d.y() * d.y() + q.zz * d.z() * d.z()
+            + q.xy * d.x() * d.y() + q.yz * d.y() * d.z() + q.xz * d.x() * d.z();
+        let b = self.gradient(o).dot(d);
+        let c = q.xx * o.x() * o.x() + q.yy * o.y() * o.y() + q.zz * o.z() * o.z()
+            + q.xy * o.x() * o.y() + q.yz * o.y() * o.z() + q.xz * o.x() * o.z()
+            + q.x * o.x() + q.y * o.y() + q.z * o.z() + q.constant;
+        let (t, point) = solve_quadratic(a as f64, b as f64, c as f64).into_iter()
+            .map(|t| (t as f32, ray.at(t as f32)))
+            .find(|&(t, point)| t > t_min && t < t_max && self.bounds.contains(point))?;
+
+        let gradient = self.gradient(point);
+        if gradient.length_square() == 0.0 {
+            return None;
+        }
+        let (u, v) = sphere_uv(Vec3::unit_vector(point - self.bounds.centroid()));
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = point;
+        hit_record.u = u;
+        hit_record.v = v;
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, Vec3::unit_vector(gradient)))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
@@ -4,21 +4,38 @@ use crate::algebra::vec3::{Point3, Vec3};
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+    time: f32,
+    wavelength: Option<f32>,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Ray {
+        Ray::with_time(origin, direction, 0.0)
+    }
+    pub fn with_time(origin: Point3, direction: Vec3, time: f32) -> Ray {
         Ray {
             origin,
             direction,
+            time,
+            wavelength: None,
         }
     }
+    pub fn with_wavelength(mut self, wavelength: Option<f32>) -> Ray {
+        self.wavelength = wavelength;
+        self
+    }
     pub fn direction(&self) -> Vec3 {
         self.direction
     }
     pub fn origin(&self) -> Point3 {
         self.origin
     }
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    pub fn wavelength(&self) -> Option<f32> {
+        self.wavelength
+    }
     pub fn at(&self, t: f32) -> Point3 {
         self.origin + self.direction * t
     }
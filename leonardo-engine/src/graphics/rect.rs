@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_in_range_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+const BOX_PADDING: f32 = 1e-4;
+
+pub struct Rect {
+    axes: [usize; 3],
+    a0: f32,
+    a1: f32,
+    b0: f32,
+    b1: f32,
+    k: f32,
+    flipped: bool,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Rect {
+    fn new(axes: [usize; 3], (a0, a1): (f32, f32), (b0, b1): (f32, f32), k: f32, material: Option<Arc<dyn Material>>) -> Rect {
+        Rect {
+            axes,
+            a0: a0.min(a1),
+            a1: a0.max(a1),
+            b0: b0.min(b1),
+            b1: b0.max(b1),
+            k,
+            flipped: false,
+            material,
+        }
+    }
+    pub fn xy(x0: f32, x1: f32, y0: f32, y1: f32, k: f32, material: Option<Arc<dyn Material>>) -> Rect {
+        Rect::new([0, 1, 2], (x0, x1), (y0, y1), k, material)
+    }
+    pub fn xz(x0: f32, x1: f32, z0: f32, z1: f32, k: f32, material: Option<Arc<dyn Material>>) -> Rect {
+        Rect::new([0, 2, 1], (x0, x1), (z0, z1), k, material)
+    }
+    pub fn yz(y0: f32, y1: f32, z0: f32, z1: f32, k: f32, material: Option<Arc<dyn Material>>) -> Rect {
+        Rect::new([1, 2, 0], (y0, y1), (z0, z1), k, material)
+    }
+    pub fn flipped(mut self, flipped: bool) -> Rect {
+        self.flipped = flipped;
+        self
+    }
+    fn point(&self, a: f32, b: f32, k: f32) -> Point3 {
+        let mut e = [0.0; 3];
+        e[self.axes[0]] = a;
+        e[self.axes[1]] = b;
+        e[self.axes[2]] = k;
+        Point3::new(e[0], e[1], e[2])
+    }
+    fn normal(&self) -> Vec3 {
+        self.point(0.0, 0.0, if self.flipped { -1.0 } else { 1.0 })
+    }
+    fn area(&self) -> f32 {
+        (self.a1 - self.a0) * (self.b1 - self.b0)
+    }
+}
+
+impl Hittable for Rect {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let [a, b, n] = self.axes;
+        let t = (self.k - ray.origin()[n]) / ray.direction()[n];
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+        let x = ray.origin()[a] + t * ray.direction()[a];
+        let y = ray.origin()[b] + t * ray.direction()[b];
+        if x < self.a0 || x > self.a1 || y < self.b0 || y > self.b1 {
+            return None;
+        }
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = ray.at(t);
+        hit_record.u = (x - self.a0) / (self.a1 - self.a0);
+        hit_record.v = (y - self.b0) / (self.b1 - self.b0);
+        hit_record.dpdu = self.point(self.a1 - self.a0, 0.0, 0.0);
+        hit_record.dpdv = self.point(0.0, self.b1 - self.b0, 0.0);
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.normal()))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.point(self.a0, self.b0, self.k - BOX_PADDING),
+            self.point(self.a1, self.b1, self.k + BOX_PADDING),
+        ))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let hit_record = match self.hit(Ray::new(origin, direction), 0.001, f32::MAX, &HitRecord::new_def()) {
+            Some(hit_record) => hit_record,
+            None => return 0.0
+        };
+        let distance_squared = hit_record.t * hit_record.t * direction.length_square();
+        let cosine = direction[self.axes[2]].abs() / direction.length();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area())
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        let point = self.point(random_in_range_f32(self.a0, self.a1), random_in_range_f32(self.b0, self.b1), self.k);
+        point - origin
+    }
+}
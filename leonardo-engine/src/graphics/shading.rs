@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::ray::Ray;
+use crate::graphics::texture::Texture;
+
+const BUMP_DELTA: f32 = 1e-3;
+const MIN_COSINE: f32 = 1e-3;
+
+pub enum ShadingMap {
+    Normal {
+        texture: Arc<dyn Texture>,
+        strength: f32,
+    },
+    Bump {
+        height: Arc<dyn Texture>,
+        scale: f32,
+    },
+}
+
+fn height(texture: &Arc<dyn Texture>, u: f32, v: f32, point: Point3) -> f32 {
+    let value = texture.value(u, v, point);
+    (value.x() + value.y() + value.z()) / 3.0
+}
+
+impl ShadingMap {
+    fn normal(&self, hit_record: &HitRecord) -> Vec3 {
+        let (u, v, point) = (hit_record.u, hit_record.v, hit_record.point);
+        match self {
+            ShadingMap::Normal { texture, strength } => {
+                let value = texture.value(u, v, point);
+                let x = (2.0 * value.x() - 1.0) * strength;
+                let y = (2.0 * value.y() - 1.0) * strength;
+                let z = (2.0 * value.z() - 1.0).max(0.0);
+                hit_record.tangent * x + hit_record.bitangent * y + hit_record.shading_normal * z
+            }
+            ShadingMap::Bump { height: texture, scale } => {
+                let outward = if hit_record.front_face { hit_record.shading_normal } else { -hit_record.shading_normal };
+                let base = height(texture, u, v, point);
+                let du = height(texture, u + BUMP_DELTA, v, point + hit_record.dpdu * BUMP_DELTA) - base;
+                let dv = height(texture, u, v + BUMP_DELTA, point + hit_record.dpdv * BUMP_DELTA) - base;
+                let dpdu = hit_record.dpdu + outward * (du / BUMP_DELTA * scale);
+                let dpdv = hit_record.dpdv + outward * (dv / BUMP_DELTA * scale);
+                let normal = dpdu.cross(dpdv);
+                if normal.dot(hit_record.shading_normal) < 0.0 { -normal } else { normal }
+            }
+        }
+    }
+}
+
+pub struct Perturbed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    map: ShadingMap,
+}
+
+impl<H: Hittable + ?Sized> Perturbed<H> {
+    pub fn new(object: Arc<H>, map: ShadingMap) -> Perturbed<H> {
+        Perturbed {
+            object,
+            map,
+        }
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Perturbed<H> {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        let record = self.object.hit(ray, t_min, t_max, hit_record)?;
+        let normal = self.map.normal(&record);
+        if normal.length_square() == 0.0 {
+            return Some(record);
+        }
+        let normal = Vec3::unit_vector(normal);
+        let wo = -Vec3::unit_vector(ray.direction());
+        if normal.dot(record.normal) < MIN_COSINE || normal.dot(wo) * record.shading_normal.dot(wo) <= 0.0 {
+            return Some(record);
+        }
+        Some(record.with_shading_normal(normal))
+    }
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        self.object.hit_surface(ray, t_min, t_max, hit_record)
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        self.object.transmittance(ray, t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        self.object.bounding_box(time0, time1)
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        self.object.pdf_value(origin, direction)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.object.random(origin)
+    }
+}
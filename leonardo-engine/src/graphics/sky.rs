@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+
+use crate::algebra::distribution::Distribution2D;
+use crate::algebra::onb::Onb;
+use crate::algebra::utility::{degrees_to_radians, random_f32};
+use crate::algebra::vec3::{Color, Vec3};
+use crate::graphics::background::Background;
+use crate::graphics::environment::{equirect_direction, equirect_pdf};
+use crate::graphics::ray::Ray;
+use crate::graphics::spectrum::{cie_xyz, planck, xyz_to_linear_srgb, LAMBDA_MAX, LAMBDA_MIN};
+use crate::graphics::tonemap::luminance;
+
+pub const SUN_DIAMETER: f32 = 0.53;
+
+const SUN_TEMPERATURE: f32 = 5778.0;
+const LUMINOUS_EFFICACY: f32 = 683.0;
+const LUMINANCE_SCALE: f32 = 1.0 / 20_000.0;
+const SUN_STEPS: usize = 94;
+const DISTRIBUTION_WIDTH: usize = 128;
+const DISTRIBUTION_HEIGHT: usize = 64;
+const MIN_SUN_PROBABILITY: f32 = 0.05;
+const MAX_SUN_PROBABILITY: f32 = 0.95;
+const MIN_COS_THETA: f32 = 1e-3;
+
+#[derive(Copy, Clone, Debug)]
+struct Perez {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+}
+
+impl Perez {
+    fn new(turbidity: f32, coefficients: [[f32; 2]; 5]) -> Perez {
+        let [a, b, c, d, e] = coefficients.map(|[slope, offset]| slope * turbidity + offset);
+        Perez { a, b, c, d, e }
+    }
+    fn value(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta.max(MIN_COS_THETA)).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+fn zenith_chromaticity(turbidity: f32, theta: f32, coefficients: [[f32; 4]; 3]) -> f32 {
+    let thetas = [theta * theta * theta, theta * theta, theta, 1.0];
+    let row = |coefficients: [f32; 4]| (0..4).map(|i| coefficients[i] * thetas[i]).sum::<f32>();
+    turbidity * turbidity * row(coefficients[0]) + turbidity * row(coefficients[1]) + row(coefficients[2])
+}
+
+fn sun_transmittance(lambda: f32, turbidity: f32, theta: f32) -> f32 {
+    let micrometers = lambda / 1000.0;
+    let air_mass = 1.0 / (theta.cos() + 0.15 * (93.885 - theta.to_degrees()).powf(-1.253));
+    let rayleigh = -0.008_735 * micrometers.powf(-4.08);
+    let beta = 0.046_083_65 * turbidity - 0.045_860_25;
+    let aerosol = -beta * micrometers.powf(-1.3);
+    ((rayleigh + aerosol) * air_mass).exp()
+}
+
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    perez: [Perez; 3],
+    zenith: [f32; 3],
+    normalization: [f32; 3],
+    sun_radiance: Color,
+    cos_sun_max: f32,
+    ground: Color,
+    intensity: f32,
+    distribution: Distribution2D,
+    sun_probability: f32,
+}
+
+impl PhysicalSky {
+    pub fn new(sun_direction: Vec3, turbidity: f32, ground_albedo: Color, sun_diameter: f32) -> PhysicalSky {
+        let sun_direction = Vec3::unit_vector(sun_direction);
+        let theta_sun = sun_direction.y().clamp(MIN_COS_THETA, 1.0).acos();
+        let perez = [
+            Perez::new(turbidity, [[0.1787, -1.4630], [-0.3554, 0.4275], [-0.0227, 5.3251], [0.1206, -2.5771], [-0.0670, 0.3703]]),
+            Perez::new(turbidity, [[-0.0193, -0.2592], [-0.0665, 0.0008], [-0.0004, 0.2125], [-0.0641, -0.8989], [-0.0033, 0.0452]]),
+            Perez::new(turbidity, [[-0.0167, -0.2608], [-0.0950, 0.0092], [-0.0079, 0.2102], [-0.0441, -1.6537], [-0.0109, 0.0529]]),
+        ];
+        let chi = (4.0 / 9.0 - turbidity / 120.0) * (PI - 2.0 * theta_sun);
+        let zenith = [
+            ((4.0453 * turbidity - 4.9710) * chi.tan() - 0.2155 * turbidity + 2.4192) * 1000.0,
+            zenith_chromaticity(turbidity, theta_sun, [
+                [0.00166, -0.00375, 0.00209, 0.0],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ]),
+            zenith_chromaticity(turbidity, theta_sun, [
+                [0.00275, -0.00610, 0.00317, 0.0],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ]),
+        ];
+        let normalization = perez.map(|perez| perez.value(1.0, theta_sun));
+
+        let sun_diameter = degrees_to_radians(sun_diameter.max(1e-3));
+        let cos_sun_max = (0.5 * sun_diameter).cos();
+        let solid_angle = 2.0 * PI * (1.0 - cos_sun_max);
+        let physical_solid_angle = 2.0 * PI * (1.0 - (0.5 * degrees_to_radians(SUN_DIAMETER)).cos());
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / SUN_STEPS as f32;
+        let sun_xyz = (0..SUN_STEPS)
+            .map(|i| LAMBDA_MIN + (i as f32 + 0.5) * step)
+            .fold(Vec3::new(0.0, 0.0, 0.0), |acc, lambda| {
+                acc + cie_xyz(lambda) * (planck(lambda, SUN_TEMPERATURE) * sun_transmittance(lambda, turbidity, theta_sun))
+            }) * (step * 1e-9 * LUMINOUS_EFFICACY * LUMINANCE_SCALE * physical_solid_angle / solid_angle);
+        let sun_radiance = if sun_direction.y() > 0.0 { clamp_negative(xyz_to_linear_srgb(sun_xyz)) } else { Color::new(0.0, 0.0, 0.0) };
+
+        let mut sky = PhysicalSky {
+            sun_direction,
+            perez,
+            zenith,
+            normalization,
+            sun_radiance,
+            cos_sun_max,
+            ground: Color::new(0.0, 0.0, 0.0),
+            intensity: 1.0,
+            distribution: Distribution2D::new(&[1.0], 1, 1),
+            sun_probability: 0.0,
+        };
+
+        let cell = 2.0 * PI * PI / (DISTRIBUTION_WIDTH * DISTRIBUTION_HEIGHT) as f32;
+        let cells: Vec<(Vec3, f32)> = (0..DISTRIBUTION_WIDTH * DISTRIBUTION_HEIGHT)
+            .map(|i| {
+                let u = ((i % DISTRIBUTION_WIDTH) as f32 + 0.5) / DISTRIBUTION_WIDTH as f32;
+                let v = ((i / DISTRIBUTION_WIDTH) as f32 + 0.5) / DISTRIBUTION_HEIGHT as f32;
+                (equirect_direction(u, v), (PI * v).sin())
+            })
+            .collect();
+        let irradiance = cells.iter()
+            .filter(|(direction, _)| direction.y() > 0.0)
+            .fold(sun_radiance * (solid_angle * sun_direction.y().max(0.0)), |acc, &(direction, sin_theta)| {
+                acc + sky.sky(direction) * (direction.y() * sin_theta * cell)
+            });
+        sky.ground = Color::new(
+            ground_albedo.x() * irradiance.x(),
+            ground_albedo.y() * irradiance.y(),
+            ground_albedo.z() * irradiance.z(),
+        ) / PI;
+
+        let weights: Vec<f32> = cells.iter()
+            .map(|&(direction, sin_theta)| {
+                let radiance = if direction.y() > 0.0 { sky.sky(direction) } else { sky.ground };
+                luminance(radiance) * sin_theta
+            })
+            .collect();
+        let sky_power = weights.iter().sum::<f32>() * cell;
+        let sun_power = luminance(sun_radiance) * solid_angle;
+        sky.distribution = Distribution2D::new(&weights, DISTRIBUTION_WIDTH, DISTRIBUTION_HEIGHT);
+        sky.sun_probability = if sun_power > 0.0 {
+            (sun_power / (sun_power + sky_power)).clamp(MIN_SUN_PROBABILITY, MAX_SUN_PROBABILITY)
+        } else {
+            0.0
+        };
+        sky
+    }
+    pub fn with_intensity(mut self, intensity: f32) -> PhysicalSky {
+        self.intensity = intensity;
+        self
+    }
+    fn sky(&self, direction: Vec3) -> Color {
+        let cos_theta = direction.y();
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2].map(|i| self.zenith[i] * self.perez[i].value(cos_theta, gamma) / self.normalization[i]);
+        if y <= 0.0 || luminance <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let xyz = Vec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        clamp_negative(xyz_to_linear_srgb(xyz * LUMINANCE_SCALE))
+    }
+    fn sun_pdf(&self) -> f32 {
+        1.0 / (2.0 * PI * (1.0 - self.cos_sun_max))
+    }
+}
+
+fn clamp_negative(color: Color) -> Color {
+    Color::new(color.x().max(0.0), color.y().max(0.0), color.z().max(0.0))
+}
+
+impl Background for PhysicalSky {
+    fn color(&self, ray: Ray) -> Color {
+        let direction = Vec3::unit_vector(ray.direction());
+        if direction.y() < 0.0 {
+            return self.ground * self.intensity;
+        }
+        let mut color = self.sky(direction);
+        if direction.dot(self.sun_direction) >= self.cos_sun_max {
+            color = color + self.sun_radiance;
+        }
+        color * self.intensity
+    }
+    fn pdf_value(&self, direction: Vec3) -> f32 {
+        let direction = Vec3::unit_vector(direction);
+        let sun = if direction.dot(self.sun_direction) >= self.cos_sun_max { self.sun_pdf() } else { 0.0 };
+        self.sun_probability * sun + (1.0 - self.sun_probability) * equirect_pdf(&self.distribution, direction)
+    }
+    fn random(&self) -> Vec3 {
+        if random_f32() < self.sun_probability {
+            let cos_theta = 1.0 - random_f32() * (1.0 - self.cos_sun_max);
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * random_f32();
+            return Onb::new(self.sun_direction).local(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta));
+        }
+        let (u, v, _) = self.distribution.sample(random_f32(), random_f32());
+        equirect_direction(u, v)
+    }
+    fn is_sampled(&self) -> bool {
+        self.intensity > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::utility::seed_rng;
+    use crate::algebra::vec3::Point3;
+    use crate::graphics::background::assert_sampling_matches_uniform;
+
+    fn afternoon() -> PhysicalSky {
+        PhysicalSky::new(Vec3::new(0.4, 0.5, -0.6), 3.0, Color::new(0.3, 0.3, 0.3), 2.0)
+    }
+
+    #[test]
+    fn zenith_is_blue_and_sun_is_warm() {
+        let sky = afternoon();
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let zenith = sky.color(Ray::new(origin, Vec3::new(0.0, 1.0, 0.0)));
+        assert!(zenith.z() > zenith.x(), "{}", zenith);
+        let sun = sky.color(Ray::new(origin, sky.sun_direction)) - zenith;
+        assert!(sun.x() > sun.z() && luminance(sun) > 1e3 * luminance(zenith), "{}", sun);
+    }
+
+    #[test]
+    fn importance_sampling_matches_uniform_estimate() {
+        seed_rng(3);
+        let sky = afternoon();
+        let sun = (sky.sun_direction, sky.cos_sun_max, luminance(sky.sun_radiance));
+        assert_sampling_matches_uniform(&sky, Some(sun), 0.03);
+    }
+}
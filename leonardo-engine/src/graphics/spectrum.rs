@@ -0,0 +1,268 @@
+use std::ops::{Add, Mul};
+use std::sync::OnceLock;
+
+use crate::algebra::vec3::{Color, Vec3};
+use crate::graphics::tonemap::luminance;
+
+pub const LAMBDA_MIN: f32 = 360.0;
+pub const LAMBDA_MAX: f32 = 830.0;
+
+const PLANCK: f64 = 6.626_070_15e-34;
+const BOLTZMANN: f64 = 1.380_649e-23;
+const LIGHT_SPEED: f64 = 299_792_458.0;
+const REFERENCE_TEMPERATURE: f32 = 1000.0;
+const BLACKBODY_STEPS: usize = 94;
+
+pub const WAVELENGTH_SAMPLES: usize = 4;
+
+const SMITS_MIN: f32 = 380.0;
+const SMITS_MAX: f32 = 720.0;
+const SMITS_BINS: usize = 10;
+const SMITS_WHITE: [f32; SMITS_BINS] = [1.0, 1.0, 0.9999, 0.9993, 0.9992, 0.9998, 1.0, 1.0, 1.0, 1.0];
+const SMITS_CYAN: [f32; SMITS_BINS] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0, 0.0, 0.0];
+const SMITS_MAGENTA: [f32; SMITS_BINS] = [1.0, 1.0, 0.9685, 0.2229, 0.0, 0.0458, 0.8369, 1.0, 1.0, 0.9959];
+const SMITS_YELLOW: [f32; SMITS_BINS] = [0.0001, 0.0, 0.1088, 0.6651, 1.0, 1.0, 0.9996, 0.9586, 0.9685, 0.9840];
+const SMITS_RED: [f32; SMITS_BINS] = [0.1012, 0.0515, 0.0, 0.0, 0.0, 0.0, 0.8325, 1.0149, 1.0149, 1.0149];
+const SMITS_GREEN: [f32; SMITS_BINS] = [0.0, 0.0, 0.0273, 0.7937, 1.0, 0.9418, 0.1719, 0.0, 0.0, 0.0025];
+const SMITS_BLUE: [f32; SMITS_BINS] = [1.0, 1.0, 0.8916, 0.3323, 0.0, 0.0, 0.0003, 0.0369, 0.0483, 0.0496];
+const WHITE_BALANCE_STEPS: usize = 470;
+
+fn lobe(lambda: f32, mean: f32, below: f32, above: f32) -> f32 {
+    let t = (lambda - mean) / if lambda < mean { below } else { above };
+    (-0.5 * t * t).exp()
+}
+
+pub fn cie_xyz(lambda: f32) -> Vec3 {
+    Vec3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Color {
+    Color::new(
+        3.240_454 * xyz.x() - 1.537_139 * xyz.y() - 0.498_531 * xyz.z(),
+        -0.969_266 * xyz.x() + 1.876_011 * xyz.y() + 0.041_556 * xyz.z(),
+        0.055_643 * xyz.x() - 0.204_026 * xyz.y() + 1.057_225 * xyz.z(),
+    )
+}
+
+pub fn planck(lambda: f32, temperature: f32) -> f32 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+    let lambda = lambda as f64 * 1e-9;
+    let exponent = PLANCK * LIGHT_SPEED / (lambda * BOLTZMANN * temperature as f64);
+    (2.0 * PLANCK * LIGHT_SPEED * LIGHT_SPEED / (lambda.powi(5) * exponent.exp_m1())) as f32
+}
+
+pub fn blackbody(temperature: f32) -> Color {
+    if temperature <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let step = (LAMBDA_MAX - LAMBDA_MIN) / BLACKBODY_STEPS as f32;
+    let xyz = (0..BLACKBODY_STEPS)
+        .map(|i| LAMBDA_MIN + (i as f32 + 0.5) * step)
+        .fold(Vec3::new(0.0, 0.0, 0.0), |acc, lambda| acc + cie_xyz(lambda) * planck(lambda, temperature));
+    let rgb = xyz_to_linear_srgb(xyz);
+    let rgb = Color::new(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0));
+    let l = luminance(rgb);
+    if l <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    rgb * ((temperature / REFERENCE_TEMPERATURE).powi(4) / l)
+}
+
+fn smits_bin(lambda: f32) -> usize {
+    let t = (lambda - SMITS_MIN) / (SMITS_MAX - SMITS_MIN) * SMITS_BINS as f32;
+    (t.max(0.0) as usize).min(SMITS_BINS - 1)
+}
+
+fn smits(r: f32, g: f32, b: f32, bin: usize) -> f32 {
+    let white = SMITS_WHITE[bin];
+    if r <= g && r <= b {
+        if g <= b {
+            r * white + (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            r * white + (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        if r <= b {
+            g * white + (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            g * white + (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        }
+    } else if r <= g {
+        b * white + (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+    } else {
+        b * white + (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+    }
+}
+
+fn white_balance() -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / WHITE_BALANCE_STEPS as f32;
+        let xyz = (0..WHITE_BALANCE_STEPS)
+            .map(|i| LAMBDA_MIN + (i as f32 + 0.5) * step)
+            .fold(Vec3::new(0.0, 0.0, 0.0), |acc, lambda| acc + cie_xyz(lambda) * step);
+        xyz_to_linear_srgb(xyz)
+    })
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampledSpectrum([f32; WAVELENGTH_SAMPLES]);
+
+impl SampledSpectrum {
+    pub fn new(values: [f32; WAVELENGTH_SAMPLES]) -> SampledSpectrum {
+        SampledSpectrum(values)
+    }
+    pub fn constant(value: f32) -> SampledSpectrum {
+        SampledSpectrum([value; WAVELENGTH_SAMPLES])
+    }
+    pub fn from_rgb(rgb: Color, wavelengths: &SampledWavelengths) -> SampledSpectrum {
+        let (r, g, b) = (rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0));
+        let scale = r.max(g).max(b);
+        if scale <= 0.0 {
+            return SampledSpectrum::constant(0.0);
+        }
+        let scale = scale.max(1.0);
+        let (r, g, b) = (r / scale, g / scale, b / scale);
+        SampledSpectrum(wavelengths.lambda.map(|lambda| smits(r, g, b, smits_bin(lambda)).max(0.0) * scale))
+    }
+    pub fn values(&self) -> [f32; WAVELENGTH_SAMPLES] {
+        self.0
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        SampledSpectrum(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        SampledSpectrum(std::array::from_fn(|i| self.0[i] * rhs.0[i]))
+    }
+}
+
+impl Mul<f32> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        SampledSpectrum(self.0.map(|value| value * rhs))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampledWavelengths {
+    lambda: [f32; WAVELENGTH_SAMPLES],
+    pdf: [f32; WAVELENGTH_SAMPLES],
+}
+
+fn sample_visible_wavelength(u: f32) -> f32 {
+    538.0 - 138.888_89 * (0.856_910_6 - 1.827_502 * u).atanh()
+}
+
+fn visible_wavelength_pdf(lambda: f32) -> f32 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    0.003_939_804 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+impl SampledWavelengths {
+    pub fn sample_visible(u: f32) -> SampledWavelengths {
+        let lambda: [f32; WAVELENGTH_SAMPLES] = std::array::from_fn(|i| {
+            let u = (u + i as f32 / WAVELENGTH_SAMPLES as f32).fract();
+            sample_visible_wavelength(u).clamp(LAMBDA_MIN, LAMBDA_MAX)
+        });
+        SampledWavelengths {
+            lambda,
+            pdf: lambda.map(visible_wavelength_pdf),
+        }
+    }
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+    pub fn lambda(&self) -> [f32; WAVELENGTH_SAMPLES] {
+        self.lambda
+    }
+    pub fn terminate_secondary(mut self) -> SampledWavelengths {
+        self.pdf[0] /= WAVELENGTH_SAMPLES as f32;
+        for pdf in self.pdf.iter_mut().skip(1) {
+            *pdf = 0.0;
+        }
+        self
+    }
+    pub fn to_rgb(&self, radiance: SampledSpectrum) -> Color {
+        let xyz = (0..WAVELENGTH_SAMPLES)
+            .filter(|&i| self.pdf[i] > 0.0)
+            .fold(Vec3::new(0.0, 0.0, 0.0), |acc, i| acc + cie_xyz(self.lambda[i]) * (radiance.0[i] / self.pdf[i]));
+        let rgb = xyz_to_linear_srgb(xyz / WAVELENGTH_SAMPLES as f32);
+        let white = white_balance();
+        Color::new(rgb.x() / white.x(), rgb.y() / white.y(), rgb.z() / white.z())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: usize = 20_000;
+
+    fn round_trip(rgb: Color) -> Color {
+        (0..SAMPLES)
+            .map(|i| {
+                let wavelengths = SampledWavelengths::sample_visible((i as f32 + 0.5) / SAMPLES as f32);
+                wavelengths.to_rgb(SampledSpectrum::from_rgb(rgb, &wavelengths))
+            })
+            .fold(Color::new(0.0, 0.0, 0.0), |acc, color| acc + color) / SAMPLES as f32
+    }
+
+    #[test]
+    fn white_round_trips() {
+        let color = round_trip(Color::new(1.0, 1.0, 1.0));
+        for i in 0..3 {
+            assert!((color[i] - 1.0).abs() < 0.02, "{}", color);
+        }
+    }
+
+    #[test]
+    fn emission_scales_linearly() {
+        let color = round_trip(Color::new(4.0, 4.0, 4.0));
+        for i in 0..3 {
+            assert!((color[i] - 4.0).abs() < 0.08, "{}", color);
+        }
+    }
+
+    #[test]
+    fn primaries_round_trip() {
+        for rgb in [Color::new(1.0, 0.0, 0.0), Color::new(0.0, 1.0, 0.0), Color::new(0.0, 0.0, 1.0)].iter() {
+            let color = round_trip(*rgb);
+            for i in 0..3 {
+                assert!((color[i] - rgb[i]).abs() < 0.03, "{}", color);
+            }
+        }
+    }
+
+    #[test]
+    fn terminated_wavelengths_keep_the_estimate_unbiased() {
+        let color = (0..SAMPLES)
+            .map(|i| {
+                let wavelengths = SampledWavelengths::sample_visible((i as f32 + 0.5) / SAMPLES as f32).terminate_secondary();
+                wavelengths.to_rgb(SampledSpectrum::constant(1.0))
+            })
+            .fold(Color::new(0.0, 0.0, 0.0), |acc, color| acc + color) / SAMPLES as f32;
+        for i in 0..3 {
+            assert!((color[i] - 1.0).abs() < 0.02, "{}", color);
+        }
+    }
+}
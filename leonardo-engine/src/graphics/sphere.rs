@@ -1,6 +1,10 @@
-use std::rc::Rc;
+use std::f32::consts::PI;
+use std::sync::Arc;
 
-use crate::algebra::vec3::Point3;
+use crate::algebra::onb::Onb;
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
 use crate::graphics::hit::{HitRecord, Hittable};
 use crate::graphics::material::Material;
 use crate::graphics::ray::Ray;
@@ -8,58 +12,103 @@ use crate::graphics::ray::Ray;
 pub struct Sphere {
     center: Point3,
     radius: f32,
-    material: Option<Rc<dyn Material>>,
+    material: Option<Arc<dyn Material>>,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f32, material: Option<Rc<dyn Material>>) -> Sphere {
+    pub fn new(center: Point3, radius: f32, material: Option<Arc<dyn Material>>) -> Sphere {
         Sphere {
             center,
             radius,
             material,
         }
     }
-    fn compute_normal(&self, ray: Ray, temp: f32, t_max: f32, t_min: f32) -> Option<HitRecord> {
-        if temp < t_max && temp > t_min {
-            return get_new_record(ray, self.center, self.radius, temp, self.material.clone());
+    fn one_minus_cos_theta_max(&self, origin: Point3) -> Option<f32> {
+        let sin_theta_max_squared = self.radius * self.radius / (self.center - origin).length_square();
+        if sin_theta_max_squared >= 1.0 {
+            return None;
         }
-        None
+        Some(sin_theta_max_squared / (1.0 + (1.0 - sin_theta_max_squared).sqrt()))
     }
 }
 
-fn get_new_record(ray: Ray, center: Point3, radius: f32, temp: f32, material: Option<Rc<dyn Material>>) -> Option<HitRecord> {
+pub(crate) fn hit_sphere(center: Point3, radius: f32, material: &Option<Arc<dyn Material>>, ray: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    let oc = ray.origin() - center;
+    let a = ray.direction().length_square();
+    let half_b = oc.dot(ray.direction());
+    let c = oc.length_square() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant > 0.0 {
+        let root = discriminant.sqrt();
+        for temp in [(-half_b - root) / a, (-half_b + root) / a] {
+            if temp < t_max && temp > t_min {
+                return get_new_record(ray, center, radius, temp, material.clone());
+            }
+        }
+    }
+    None
+}
+
+pub(crate) fn sphere_uv(point: Vec3) -> (f32, f32) {
+    let theta = (-point.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-point.z()).atan2(point.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+fn get_new_record(ray: Ray, center: Point3, radius: f32, temp: f32, material: Option<Arc<dyn Material>>) -> Option<HitRecord> {
     let mut new_hit_record = HitRecord::new_def();
     new_hit_record.t = temp;
     new_hit_record.point = ray.at(temp);
     new_hit_record.material = material;
     let outward_normal = (new_hit_record.point - center) / radius;
-    return Some(
+    let (u, v) = sphere_uv(outward_normal);
+    new_hit_record.u = u;
+    new_hit_record.v = v;
+    let offset = new_hit_record.point - center;
+    let ring = (offset.x() * offset.x() + offset.z() * offset.z()).sqrt();
+    if ring > 0.0 {
+        new_hit_record.dpdu = Vec3::new(offset.z(), 0.0, -offset.x()) * (2.0 * PI);
+        new_hit_record.dpdv = Vec3::new(-offset.y() * offset.x() / ring, ring, -offset.y() * offset.z() / ring) * PI;
+    }
+    Some(
         HitRecord::with_normal(
             new_hit_record,
             ray,
             outward_normal,
         )
-    );
+    )
+}
+
+fn random_to_sphere(one_minus_cos_theta_max: f32) -> Vec3 {
+    let phi = 2.0 * PI * random_f32();
+    let z = 1.0 - random_f32() * one_minus_cos_theta_max;
+    let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+    Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
 }
 
 impl Hittable for Sphere {
     fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_square();
-        let half_b = oc.dot(ray.direction());
-        let c = oc.length_square() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        if discriminant > 0.0 {
-            let root = discriminant.sqrt();
-            let mut temp = (-half_b - root) / a;
-            return match self.compute_normal(ray, temp, t_max, t_min) {
-                Some(hrec) => Some(hrec),
-                None => {
-                    temp = (-half_b + root) / a;
-                    self.compute_normal(ray, temp, t_max, t_min)
-                }
-            };
+        hit_sphere(self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let r = self.radius.abs();
+        let extent = Vec3::new(r, r, r);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let one_minus_cos_theta_max = match self.one_minus_cos_theta_max(origin) {
+            Some(value) => value,
+            None => return 1.0 / (4.0 * PI)
+        };
+        if self.hit(Ray::new(origin, direction), 0.001, f32::MAX, &HitRecord::new_def()).is_none() {
+            return 0.0;
         }
-        return None;
+        1.0 / (2.0 * PI * one_minus_cos_theta_max)
     }
-}
\ No newline at end of file
+    fn random(&self, origin: Point3) -> Vec3 {
+        match self.one_minus_cos_theta_max(origin) {
+            Some(one_minus_cos_theta_max) => Onb::new(self.center - origin).local(random_to_sphere(one_minus_cos_theta_max)),
+            None => Vec3::random_unit_vector()
+        }
+    }
+}
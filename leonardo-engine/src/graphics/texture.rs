@@ -0,0 +1,426 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algebra::noise::{Perlin, Worley};
+use crate::algebra::vec3::{Color, Point3};
+use crate::graphics::exr::decode_exr;
+use crate::graphics::hdr::decode_hdr;
+use crate::graphics::tonemap::srgb_eotf;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color;
+}
+
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> SolidColor {
+        SolidColor {
+            color
+        }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _point: Point3) -> Color {
+        self.color
+    }
+}
+
+fn is_even(cells: [f32; 3]) -> bool {
+    let sum: i64 = cells.iter().map(|cell| cell.floor() as i64).sum();
+    sum.rem_euclid(2) == 0
+}
+
+pub struct Checker {
+    odd: Arc<dyn Texture>,
+    even: Arc<dyn Texture>,
+    scale: f32,
+}
+
+impl Checker {
+    pub fn new(odd: Arc<dyn Texture>, even: Arc<dyn Texture>, scale: f32) -> Checker {
+        Checker {
+            odd,
+            even,
+            scale,
+        }
+    }
+    pub fn from_colors(odd: Color, even: Color, scale: f32) -> Checker {
+        Checker::new(Arc::new(SolidColor::new(odd)), Arc::new(SolidColor::new(even)), scale)
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color {
+        let scaled = point * self.scale;
+        if is_even([scaled.x(), scaled.y(), scaled.z()]) {
+            return self.even.value(u, v, point);
+        }
+        self.odd.value(u, v, point)
+    }
+}
+
+pub struct UvChecker {
+    odd: Arc<dyn Texture>,
+    even: Arc<dyn Texture>,
+    scale: f32,
+}
+
+impl UvChecker {
+    pub fn new(odd: Arc<dyn Texture>, even: Arc<dyn Texture>, scale: f32) -> UvChecker {
+        UvChecker {
+            odd,
+            even,
+            scale,
+        }
+    }
+    pub fn from_colors(odd: Color, even: Color, scale: f32) -> UvChecker {
+        UvChecker::new(Arc::new(SolidColor::new(odd)), Arc::new(SolidColor::new(even)), scale)
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: f32, v: f32, point: Point3) -> Color {
+        if is_even([u * self.scale, v * self.scale, 0.0]) {
+            return self.even.value(u, v, point);
+        }
+        self.odd.value(u, v, point)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    Perlin,
+    #[default]
+    Fbm,
+    Turbulence,
+    Worley,
+}
+
+pub struct NoiseTexture {
+    perlin: Perlin,
+    worley: Worley,
+    kind: NoiseKind,
+    scale: f32,
+    octaves: u32,
+    low: Color,
+    high: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(kind: NoiseKind, scale: f32, octaves: u32, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            perlin: Perlin::new(seed),
+            worley: Worley::new(seed),
+            kind,
+            scale,
+            octaves,
+            low: Color::new(0.0, 0.0, 0.0),
+            high: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+    pub fn with_colors(mut self, low: Color, high: Color) -> NoiseTexture {
+        self.low = low;
+        self.high = high;
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let p = point * self.scale;
+        let t = match self.kind {
+            NoiseKind::Perlin => 0.5 * (1.0 + self.perlin.noise(p)),
+            NoiseKind::Fbm => 0.5 * (1.0 + self.perlin.fbm(p, self.octaves)),
+            NoiseKind::Turbulence => self.perlin.turbulence(p, self.octaves),
+            NoiseKind::Worley => self.worley.fbm(p, self.octaves),
+        };
+        let t = t.clamp(0.0, 1.0);
+        self.low * (1.0 - t) + self.high * t
+    }
+}
+
+pub struct MarbleTexture {
+    perlin: Perlin,
+    scale: f32,
+    octaves: u32,
+    vein: Color,
+    base: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(scale: f32, octaves: u32, seed: u64) -> MarbleTexture {
+        MarbleTexture {
+            perlin: Perlin::new(seed),
+            scale,
+            octaves,
+            vein: Color::new(0.0, 0.0, 0.0),
+            base: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+    pub fn with_colors(mut self, vein: Color, base: Color) -> MarbleTexture {
+        self.vein = vein;
+        self.base = base;
+        self
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f32, _v: f32, point: Point3) -> Color {
+        let phase = self.scale * point.z() + 10.0 * self.perlin.turbulence(point, self.octaves);
+        let t = 0.5 * (1.0 + phase.sin());
+        self.vein * (1.0 - t) + self.base * t
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    fn apply(self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let period = index.rem_euclid(2 * size);
+                if period >= size { 2 * size - 1 - period } else { period }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    wrap: WrapMode,
+    filter: Filter,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, texels: Vec<Color>) -> ImageTexture {
+        ImageTexture {
+            width,
+            height,
+            texels,
+            wrap: WrapMode::default(),
+            filter: Filter::default(),
+        }
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ImageTexture> {
+        ImageTexture::decode(path.as_ref(), srgb_eotf)
+    }
+    pub fn load_linear<P: AsRef<Path>>(path: P) -> io::Result<ImageTexture> {
+        ImageTexture::decode(path.as_ref(), |value| value)
+    }
+    fn decode(path: &Path, transfer: fn(f32) -> f32) -> io::Result<ImageTexture> {
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let result = match extension.as_deref() {
+            Some("png") => decode_png(path, transfer),
+            Some("jpg") | Some("jpeg") => decode_jpeg(path, transfer),
+            Some("hdr") => fs::read(path).and_then(|data| decode_hdr(&data))
+                .map(|(width, height, texels)| ImageTexture::new(width, height, texels)),
+            Some("exr") => decode_exr(path).map(|(width, height, texels)| ImageTexture::new(width, height, texels)),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "unsupported image format")),
+        };
+        result.map_err(|err| match err.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => {
+                io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+            }
+            _ => err
+        })
+    }
+    pub fn with_wrap(mut self, wrap: WrapMode) -> ImageTexture {
+        self.wrap = wrap;
+        self
+    }
+    pub fn with_filter(mut self, filter: Filter) -> ImageTexture {
+        self.filter = filter;
+        self
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn texels(&self) -> &[Color] {
+        &self.texels
+    }
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _point: Point3) -> Color {
+        if self.texels.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+                let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn to_texels(samples: &[f32], channels: usize) -> Vec<Color> {
+    samples.chunks_exact(channels)
+        .map(|texel| match channels {
+            1 | 2 => Color::new(texel[0], texel[0], texel[0]),
+            _ => Color::new(texel[0], texel[1], texel[2]),
+        })
+        .collect()
+}
+
+fn decode_png(path: &Path, transfer: fn(f32) -> f32) -> io::Result<ImageTexture> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(invalid_data)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(invalid_data)?;
+    let channels = info.color_type.samples();
+    let samples: Vec<f32> = match info.bit_depth {
+        png::BitDepth::Sixteen => buffer[..info.buffer_size()].chunks_exact(2)
+            .map(|bytes| transfer(u16::from_be_bytes([bytes[0], bytes[1]]) as f32 / 65535.0))
+            .collect(),
+        _ => buffer[..info.buffer_size()].iter()
+            .map(|&byte| transfer(byte as f32 / 255.0))
+            .collect(),
+    };
+    let texels = to_texels(&samples, channels);
+    Ok(ImageTexture::new(info.width as usize, info.height as usize, texels))
+}
+
+fn decode_jpeg(path: &Path, transfer: fn(f32) -> f32) -> io::Result<ImageTexture> {
+    let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(File::open(path)?));
+    let pixels = decoder.decode().map_err(invalid_data)?;
+    let info = decoder.info().ok_or_else(|| invalid_data("missing image header"))?;
+    let (channels, samples): (usize, Vec<f32>) = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => (1, pixels.iter().map(|&byte| transfer(byte as f32 / 255.0)).collect()),
+        jpeg_decoder::PixelFormat::L16 => (1, pixels.chunks_exact(2)
+            .map(|bytes| transfer(u16::from_ne_bytes([bytes[0], bytes[1]]) as f32 / 65535.0))
+            .collect()),
+        jpeg_decoder::PixelFormat::RGB24 => (3, pixels.iter().map(|&byte| transfer(byte as f32 / 255.0)).collect()),
+        jpeg_decoder::PixelFormat::CMYK32 => return Err(invalid_data("CMYK images are not supported")),
+    };
+    let texels = to_texels(&samples, channels);
+    Ok(ImageTexture::new(info.width as usize, info.height as usize, texels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn ramp(width: usize, height: usize) -> ImageTexture {
+        let texels = (0..width * height).map(|i| Color::new(i as f32, 0.0, 0.0)).collect();
+        ImageTexture::new(width, height, texels)
+    }
+
+    fn sample(texture: &ImageTexture, u: f32, v: f32) -> f32 {
+        texture.value(u, v, Point3::new(0.0, 0.0, 0.0)).x()
+    }
+
+    #[test]
+    fn wrap_modes_resolve_out_of_range_coordinates() {
+        let cases = [
+            (WrapMode::Repeat, [2.0, 0.0, 1.0, 1.0]),
+            (WrapMode::Clamp, [0.0, 2.0, 2.0, 0.0]),
+            (WrapMode::Mirror, [0.0, 2.0, 1.0, 1.0]),
+        ];
+        for &(wrap, expected) in cases.iter() {
+            let texture = ramp(3, 1).with_filter(Filter::Nearest).with_wrap(wrap);
+            for (&u, &value) in [-0.1, 1.2, 1.5, -0.5].iter().zip(expected.iter()) {
+                assert_eq!(sample(&texture, u, 0.5), value, "{:?} at u = {}", wrap, u);
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_picks_a_texel_and_bilinear_blends_neighbours() {
+        let nearest = ramp(2, 2).with_filter(Filter::Nearest);
+        let bilinear = ramp(2, 2);
+        assert_eq!(sample(&nearest, 0.5, 0.5), 3.0);
+        assert_eq!(sample(&bilinear, 0.5, 0.5), 1.5);
+        assert_eq!(sample(&bilinear, 0.25, 0.75), 0.0);
+        assert_eq!(sample(&bilinear, 0.75, 0.25), 3.0);
+        assert!((sample(&bilinear, 0.5, 0.75) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bilinear_filtering_wraps_at_the_border() {
+        let cases = [(WrapMode::Repeat, 1.5), (WrapMode::Clamp, 0.0), (WrapMode::Mirror, 0.0)];
+        for &(wrap, expected) in cases.iter() {
+            let texture = ramp(2, 2).with_wrap(wrap);
+            assert!((sample(&texture, 0.0, 1.0) - expected).abs() < 1e-6, "{:?}", wrap);
+        }
+    }
+
+    #[test]
+    fn sixteen_bit_jpeg_samples_keep_their_byte_order() {
+        // 1x1 lossless JPEG (SOF3) with 16-bit precision holding 0x1234
+        let jpeg: &[u8] = &[
+            0xff, 0xd8,
+            0xff, 0xc3, 0x00, 0x0b, 0x10, 0x00, 0x01, 0x00, 0x01, 0x01, 0x01, 0x11, 0x00,
+            0xff, 0xc4, 0x00, 0x14, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x0f,
+            0xff, 0xda, 0x00, 0x08, 0x01, 0x01, 0x00, 0x01, 0x00, 0x00,
+            0x12, 0x33,
+            0xff, 0xd9,
+        ];
+        let path = env::temp_dir().join(format!("leonardo-l16-{}.jpg", std::process::id()));
+        fs::write(&path, jpeg).unwrap();
+        let texture = ImageTexture::load_linear(&path);
+        fs::remove_file(&path).unwrap();
+        let texture = texture.unwrap();
+        assert_eq!((texture.width(), texture.height()), (1, 1));
+        assert!((texture.texels()[0].x() - 0x1234 as f32 / 65535.0).abs() < 1e-6, "{}", texture.texels()[0]);
+    }
+}
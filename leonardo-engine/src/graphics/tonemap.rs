@@ -0,0 +1,210 @@
+use crate::algebra::vec3::Color;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    Clamp,
+    Reinhard,
+    ReinhardExtended { white_point: f32 },
+    Hable,
+    AcesFitted,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFunction {
+    Srgb,
+    Gamma2,
+    Linear,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DisplayTransform {
+    pub exposure: f32,
+    pub operator: ToneMapOperator,
+    pub transfer: TransferFunction,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        DisplayTransform {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+            transfer: TransferFunction::Srgb,
+        }
+    }
+}
+
+impl DisplayTransform {
+    pub fn new(exposure: f32, operator: ToneMapOperator, transfer: TransferFunction) -> DisplayTransform {
+        DisplayTransform {
+            exposure,
+            operator,
+            transfer,
+        }
+    }
+    pub fn apply(&self, color: Color) -> Color {
+        let exposed = color * 2.0_f32.powf(self.exposure);
+        let mapped = tone_map(self.operator, exposed);
+        Color::new(
+            encode(self.transfer, mapped.x()),
+            encode(self.transfer, mapped.y()),
+            encode(self.transfer, mapped.z()),
+        )
+    }
+}
+
+pub fn luminance(color: Color) -> f32 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+fn scale_luminance(color: Color, mapped_luminance: impl Fn(f32) -> f32) -> Color {
+    let l = luminance(color);
+    if l <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    color * (mapped_luminance(l) / l)
+}
+
+fn hable_partial(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+fn hable(x: f32) -> f32 {
+    const EXPOSURE_BIAS: f32 = 2.0;
+    const WHITE_POINT: f32 = 11.2;
+    hable_partial(x * EXPOSURE_BIAS) / hable_partial(WHITE_POINT)
+}
+
+fn mat3_mul(m: [[f32; 3]; 3], c: Color) -> Color {
+    Color::new(
+        m[0][0] * c.x() + m[0][1] * c.y() + m[0][2] * c.z(),
+        m[1][0] * c.x() + m[1][1] * c.y() + m[1][2] * c.z(),
+        m[2][0] * c.x() + m[2][1] * c.y() + m[2][2] * c.z(),
+    )
+}
+
+fn aces_fitted(color: Color) -> Color {
+    const INPUT: [[f32; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f32; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let rrt_and_odt_fit = |v: f32| (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.432951) + 0.238081);
+    let v = mat3_mul(INPUT, color);
+    mat3_mul(OUTPUT, Color::new(rrt_and_odt_fit(v.x()), rrt_and_odt_fit(v.y()), rrt_and_odt_fit(v.z())))
+}
+
+pub fn tone_map(operator: ToneMapOperator, color: Color) -> Color {
+    let color = Color::new(color.x().max(0.0), color.y().max(0.0), color.z().max(0.0));
+    let mapped = match operator {
+        ToneMapOperator::Clamp => color,
+        ToneMapOperator::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+        ToneMapOperator::ReinhardExtended { white_point } => {
+            let white_squared = white_point * white_point;
+            scale_luminance(color, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+        }
+        ToneMapOperator::Hable => Color::new(hable(color.x()), hable(color.y()), hable(color.z())),
+        ToneMapOperator::AcesFitted => aces_fitted(color),
+    };
+    Color::new(
+        mapped.x().clamp(0.0, 1.0),
+        mapped.y().clamp(0.0, 1.0),
+        mapped.z().clamp(0.0, 1.0),
+    )
+}
+
+pub fn srgb_oetf(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_eotf(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn encode(transfer: TransferFunction, x: f32) -> f32 {
+    match transfer {
+        TransferFunction::Srgb => srgb_oetf(x),
+        TransferFunction::Gamma2 => x.sqrt(),
+        TransferFunction::Linear => x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [ToneMapOperator; 5] = [
+        ToneMapOperator::Clamp,
+        ToneMapOperator::Reinhard,
+        ToneMapOperator::ReinhardExtended { white_point: 4.0 },
+        ToneMapOperator::Hable,
+        ToneMapOperator::AcesFitted,
+    ];
+
+    fn grey(x: f32) -> Color {
+        Color::new(x, x, x)
+    }
+
+    #[test]
+    fn srgb_oetf_and_eotf_are_inverses() {
+        for i in 0..=1000 {
+            let x = i as f32 / 1000.0;
+            assert!((srgb_eotf(srgb_oetf(x)) - x).abs() < 1e-5, "{}", x);
+            assert!((srgb_oetf(srgb_eotf(x)) - x).abs() < 1e-5, "{}", x);
+        }
+        for &x in &[0.0031308, 0.04045] {
+            assert!((srgb_eotf(srgb_oetf(x)) - x).abs() < 1e-6, "{}", x);
+        }
+        assert_eq!(srgb_oetf(0.0), 0.0);
+        assert!((srgb_oetf(1.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tone_mappers_send_black_to_black() {
+        for &operator in OPERATORS.iter() {
+            let mapped = tone_map(operator, grey(0.0));
+            assert!(mapped.length() < 1e-6, "{:?}: {}", operator, mapped);
+        }
+    }
+
+    #[test]
+    fn tone_mappers_are_monotonic_and_bounded() {
+        for &operator in OPERATORS.iter() {
+            let mut previous = 0.0;
+            for i in 0..=2000 {
+                let mapped = luminance(tone_map(operator, grey(i as f32 * 0.01)));
+                assert!(mapped >= previous - 1e-6, "{:?} at {}: {} < {}", operator, i as f32 * 0.01, mapped, previous);
+                assert!((0.0..=1.0).contains(&mapped), "{:?}: {}", operator, mapped);
+                previous = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn reinhard_extended_maps_the_white_point_to_one() {
+        for &white_point in &[1.0, 4.0, 11.2] {
+            let mapped = tone_map(ToneMapOperator::ReinhardExtended { white_point }, grey(white_point));
+            assert!((luminance(mapped) - 1.0).abs() < 1e-5, "{}: {}", white_point, mapped);
+        }
+        let reinhard = tone_map(ToneMapOperator::Reinhard, grey(1.0));
+        assert!((luminance(reinhard) - 0.5).abs() < 1e-6, "{}", reinhard);
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use crate::algebra::onb::Onb;
+use crate::algebra::roots::solve_quartic;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::cylinder::angle_uv;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+
+pub struct Torus {
+    center: Point3,
+    axis: Onb,
+    major_radius: f32,
+    minor_radius: f32,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Torus {
+    pub fn new(center: Point3, normal: Vec3, major_radius: f32, minor_radius: f32, material: Option<Arc<dyn Material>>) -> Torus {
+        Torus {
+            center,
+            axis: Onb::new(normal),
+            major_radius: major_radius.abs(),
+            minor_radius: minor_radius.abs(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let o = self.axis.coordinates(ray.origin() - self.center);
+        let d = self.axis.coordinates(ray.direction());
+        let (dx, dy, dz) = (d.x() as f64, d.y() as f64, d.z() as f64);
+        let a = dx * dx + dy * dy + dz * dz;
+        if a == 0.0 {
+            return None;
+        }
+        let shift = -(o.x() as f64 * dx + o.y() as f64 * dy + o.z() as f64 * dz) / a;
+        let (ox, oy, oz) = (o.x() as f64 + shift * dx, o.y() as f64 + shift * dy, o.z() as f64 + shift * dz);
+
+        let major_squared = (self.major_radius as f64).powi(2);
+        let minor_squared = (self.minor_radius as f64).powi(2);
+        let b = 2.0 * (ox * dx + oy * dy + oz * dz);
+        let c = ox * ox + oy * oy + oz * oz + major_squared - minor_squared;
+        let roots = solve_quartic(
+            a * a,
+            2.0 * a * b,
+            b * b + 2.0 * a * c - 4.0 * major_squared * (dx * dx + dy * dy),
+            2.0 * b * c - 8.0 * major_squared * (ox * dx + oy * dy),
+            c * c - 4.0 * major_squared * (ox * ox + oy * oy),
+        );
+        let t = roots.into_iter()
+            .map(|root| (root + shift) as f32)
+            .find(|&t| t > t_min && t < t_max)?;
+
+        let p = o + d * t;
+        let ring = (p.x() * p.x() + p.y() * p.y()).sqrt();
+        let tube = if ring > 0.0 {
+            Vec3::new(p.x(), p.y(), 0.0) * (self.major_radius / ring)
+        } else {
+            Vec3::new(0.0, 0.0, 0.0)
+        };
+        let normal = Vec3::unit_vector(p - tube);
+
+        let mut hit_record = HitRecord::new_def();
+        hit_record.t = t;
+        hit_record.point = ray.at(t);
+        hit_record.u = angle_uv(p.x(), p.y());
+        hit_record.v = angle_uv(ring - self.major_radius, p.z());
+        hit_record.material = self.material.clone();
+        Some(HitRecord::with_normal(hit_record, ray, self.axis.local(normal)))
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let (u, v, w) = (self.axis.u(), self.axis.v(), self.axis.w());
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3::new(
+            (u.x().abs() + v.x().abs()) * outer + w.x().abs() * self.minor_radius,
+            (u.y().abs() + v.y().abs()) * outer + w.y().abs() * self.minor_radius,
+            (u.z().abs() + v.z().abs()) * outer + w.z().abs() * self.minor_radius,
+        );
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::algebra::transform::Transform;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::ray::Ray;
+
+pub struct Transformed<H: Hittable + ?Sized> {
+    object: Arc<H>,
+    transform: Transform,
+    inverse: Transform,
+    determinant: f32,
+}
+
+impl<H: Hittable + ?Sized> Transformed<H> {
+    pub fn new(object: Arc<H>, transform: Transform) -> Transformed<H> {
+        Transformed {
+            object,
+            transform,
+            inverse: transform.inverse(),
+            determinant: transform.matrix().determinant().abs(),
+        }
+    }
+    pub fn object(&self) -> &Arc<H> {
+        &self.object
+    }
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+    fn local_ray(&self, ray: Ray) -> Ray {
+        Ray::with_time(self.inverse.point(ray.origin()), self.inverse.vector(ray.direction()), ray.time())
+            .with_wavelength(ray.wavelength())
+    }
+    fn world_record(&self, mut record: HitRecord) -> HitRecord {
+        let (dpdu, dpdv) = (self.transform.vector(record.dpdu), self.transform.vector(record.dpdv));
+        record.point = self.transform.point(record.point);
+        record.normal = Vec3::unit_vector(self.transform.normal(record.normal));
+        record.shading_normal = Vec3::unit_vector(self.transform.normal(record.shading_normal));
+        record.with_derivatives(dpdu, dpdv)
+    }
+}
+
+impl<H: Hittable + ?Sized> Hittable for Transformed<H> {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        let record = self.object.hit(self.local_ray(ray), t_min, t_max, hit_record)?;
+        Some(self.world_record(record))
+    }
+    fn hit_surface(&self, ray: Ray, t_min: f32, t_max: f32, hit_record: &HitRecord) -> Option<HitRecord> {
+        let record = self.object.hit_surface(self.local_ray(ray), t_min, t_max, hit_record)?;
+        Some(self.world_record(record))
+    }
+    fn transmittance(&self, ray: Ray, t_min: f32, t_max: f32) -> f32 {
+        self.object.transmittance(self.local_ray(ray), t_min, t_max)
+    }
+    fn bounding_box(&self, time0: f32, time1: f32) -> Option<Aabb> {
+        let local = self.object.bounding_box(time0, time1)?;
+        let (min, max) = (local.min(), local.max());
+        let mut minimum = Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut maximum = Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for corner in 0..8 {
+            let point = Point3::new(
+                if corner & 1 == 0 { min.x() } else { max.x() },
+                if corner & 2 == 0 { min.y() } else { max.y() },
+                if corner & 4 == 0 { min.z() } else { max.z() },
+            );
+            let point = self.transform.point(point);
+            minimum = Vec3::min(minimum, point);
+            maximum = Vec3::max(maximum, point);
+        }
+        Some(Aabb::new(minimum, maximum))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let local = self.inverse.vector(Vec3::unit_vector(direction));
+        let stretch = local.length();
+        self.object.pdf_value(self.inverse.point(origin), local) / (stretch * stretch * stretch * self.determinant)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.transform.vector(self.object.random(self.inverse.point(origin)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::triangle::Triangle;
+
+    struct WavelengthProbe;
+
+    impl Hittable for WavelengthProbe {
+        fn hit(&self, ray: Ray, _t_min: f32, _t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+            let wavelength = ray.wavelength().unwrap_or(-1.0);
+            Some(HitRecord::new(ray.origin(), Vec3::new(0.0, 0.0, 1.0), 1.0, wavelength, 0.0, true, None))
+        }
+        fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+            None
+        }
+    }
+
+    fn skewed() -> Transform {
+        Transform::scale(Vec3::new(3.0, 0.5, 1.5))
+            .then(Transform::rotate(Vec3::new(1.0, 2.0, -1.0), 35.0))
+            .then(Transform::translate(Vec3::new(0.5, -1.0, 2.0)))
+    }
+
+    #[test]
+    fn local_rays_keep_their_wavelength() {
+        let transformed = Transformed::new(Arc::new(WavelengthProbe), skewed());
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)).with_wavelength(Some(532.0));
+        let hit = transformed.hit(ray, 0.001, f32::MAX, &HitRecord::new_def()).unwrap();
+        assert_eq!(hit.u, 532.0);
+    }
+
+    #[test]
+    fn pdf_value_includes_the_solid_angle_jacobian() {
+        let corners = [Point3::new(-1.0, 0.0, -1.0), Point3::new(1.0, 0.0, -1.0), Point3::new(0.0, 0.0, 1.0)];
+        let local = Triangle::new(corners[0], corners[1], corners[2], None);
+        let transform = skewed();
+        let transformed = Transformed::new(Arc::new(local), transform);
+        let world = Triangle::new(transform.point(corners[0]), transform.point(corners[1]), transform.point(corners[2]), None);
+
+        let origin = transform.point(Point3::new(0.2, 4.0, 0.1));
+        for &target in &[Point3::new(0.0, 0.0, 0.0), Point3::new(0.5, 0.0, -0.8), Point3::new(-0.1, 0.0, 0.6)] {
+            let direction = transform.point(target) - origin;
+            let expected = world.pdf_value(origin, direction);
+            let actual = transformed.pdf_value(origin, direction);
+            assert!(expected > 0.0);
+            assert!((actual - expected).abs() < 1e-3 * expected, "{} != {}", actual, expected);
+        }
+    }
+}
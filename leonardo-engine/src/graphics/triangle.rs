@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use crate::algebra::utility::random_f32;
+use crate::algebra::vec3::{Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::material::Material;
+use crate::graphics::ray::Ray;
+use crate::graphics::texture::Texture;
+
+const EPSILON: f32 = 1e-8;
+const BOX_PADDING: f32 = 1e-4;
+
+pub struct Displacement {
+    pub height: Arc<dyn Texture>,
+    pub scale: f32,
+    pub subdivisions: usize,
+}
+
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f32, f32); 3]>,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Triangle {
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, material: Option<Arc<dyn Material>>) -> Triangle {
+        Triangle {
+            vertices: [p0, p1, p2],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Triangle {
+        self.normals = Some([
+            Vec3::unit_vector(normals[0]),
+            Vec3::unit_vector(normals[1]),
+            Vec3::unit_vector(normals[2]),
+        ]);
+        self
+    }
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Triangle {
+        self.uvs = Some(uvs);
+        self
+    }
+    pub fn vertices(&self) -> [Point3; 3] {
+        self.vertices
+    }
+    pub fn displaced(&self, displacement: &Displacement, directions: [Vec3; 3]) -> Vec<Triangle> {
+        let [p0, p1, p2] = self.vertices;
+        let [uv0, uv1, uv2] = self.uvs.unwrap_or([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+        let [d0, d1, d2] = directions;
+        let face_normal = (p1 - p0).cross(p2 - p0);
+        let n = displacement.subdivisions.max(1);
+        let step = 1.0 / n as f32;
+        let displace = |b1: f32, b2: f32| {
+            let b0 = 1.0 - b1 - b2;
+            let point = p0 * b0 + p1 * b1 + p2 * b2;
+            let uv = (uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2, uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2);
+            let direction = d0 * b0 + d1 * b1 + d2 * b2;
+            let direction = Vec3::unit_vector(if direction.length_square() > 0.0 { direction } else { face_normal });
+            let value = displacement.height.value(uv.0, uv.1, point);
+            let height = (value.x() + value.y() + value.z()) / 3.0;
+            (point + direction * (height * displacement.scale), uv)
+        };
+        let vertex = |i: usize, j: usize| {
+            let (b1, b2) = (i as f32 * step, j as f32 * step);
+            let (point, uv) = displace(b1, b2);
+            let dp1 = displace(b1 + step, b2).0 - displace(b1 - step, b2).0;
+            let dp2 = displace(b1, b2 + step).0 - displace(b1, b2 - step).0;
+            let normal = dp1.cross(dp2);
+            (point, if normal.length_square() > 0.0 { normal } else { face_normal }, uv)
+        };
+        let grid = (0..=n)
+            .map(|i| (0..=n - i).map(|j| vertex(i, j)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let triangle = |corners: [(usize, usize); 3]| {
+            let [a, b, c] = corners.map(|(i, j)| grid[i][j]);
+            Triangle::new(a.0, b.0, c.0, self.material.clone())
+                .with_normals([a.1, b.1, c.1])
+                .with_uvs([a.2, b.2, c.2])
+        };
+        let mut triangles = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n - i {
+                triangles.push(triangle([(i, j), (i + 1, j), (i, j + 1)]));
+                if i + j + 1 < n {
+                    triangles.push(triangle([(i + 1, j), (i + 1, j + 1), (i, j + 1)]));
+                }
+            }
+        }
+        triangles
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: Ray, t_min: f32, t_max: f32, _hit_record: &HitRecord) -> Option<HitRecord> {
+        let [p0, p1, p2] = self.vertices;
+        let edge1 = p1 - p0;
+        let edge2 = p2 - p0;
+        let pvec = ray.direction().cross(edge2);
+        let det = edge1.dot(pvec);
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let tvec = ray.origin() - p0;
+        let b1 = tvec.dot(pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let qvec = tvec.cross(edge1);
+        let b2 = ray.direction().dot(qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(qvec) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let b0 = 1.0 - b1 - b2;
+
+        let geometric_normal = Vec3::unit_vector(edge1.cross(edge2));
+        let [uv0, uv1, uv2] = self.uvs.unwrap_or([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+        let u = uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2;
+        let v = uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2;
+
+        let front_face = geometric_normal.dot(ray.direction()) < 0.0;
+        let mut hit_record = HitRecord::new(
+            ray.at(t),
+            if front_face { geometric_normal } else { -geometric_normal },
+            t,
+            u,
+            v,
+            front_face,
+            self.material.clone(),
+        );
+        let (du02, dv02) = (uv0.0 - uv2.0, uv0.1 - uv2.1);
+        let (du12, dv12) = (uv1.0 - uv2.0, uv1.1 - uv2.1);
+        let determinant = du02 * dv12 - dv02 * du12;
+        if determinant.abs() > EPSILON {
+            let (dp02, dp12) = (p0 - p2, p1 - p2);
+            hit_record = hit_record.with_derivatives(
+                (dp02 * dv12 - dp12 * dv02) / determinant,
+                (dp12 * du02 - dp02 * du12) / determinant,
+            );
+        }
+        if let Some([n0, n1, n2]) = self.normals {
+            hit_record = hit_record.with_shading_normal(n0 * b0 + n1 * b1 + n2 * b2);
+        }
+        Some(hit_record)
+    }
+    fn bounding_box(&self, _time0: f32, _time1: f32) -> Option<Aabb> {
+        let [p0, p1, p2] = self.vertices;
+        let padding = Vec3::new(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+        Some(Aabb::new(
+            Vec3::min(Vec3::min(p0, p1), p2) - padding,
+            Vec3::max(Vec3::max(p0, p1), p2) + padding,
+        ))
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        let hit_record = match self.hit(Ray::new(origin, direction), 0.001, f32::MAX, &HitRecord::new_def()) {
+            Some(hit_record) => hit_record,
+            None => return 0.0
+        };
+        let [p0, p1, p2] = self.vertices;
+        let area_normal = (p1 - p0).cross(p2 - p0);
+        let area = 0.5 * area_normal.length();
+        let distance_squared = hit_record.t * hit_record.t * direction.length_square();
+        let cosine = (area_normal.dot(direction) / (area_normal.length() * direction.length())).abs();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        distance_squared / (cosine * area)
+    }
+    fn random(&self, origin: Point3) -> Vec3 {
+        let [p0, p1, p2] = self.vertices;
+        let s = random_f32().sqrt();
+        let t = random_f32();
+        let point = p0 * (1.0 - s) + p1 * (s * (1.0 - t)) + p2 * (s * t);
+        point - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::vec3::Color;
+
+    const TOLERANCE: f32 = 1e-3;
+
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, u: f32, _v: f32, _p: Point3) -> Color {
+            Color::new(u, u, u)
+        }
+    }
+
+    fn right_triangle() -> Triangle {
+        Triangle::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), None)
+    }
+
+    fn hit_along_z(triangle: &Triangle, x: f32, y: f32, z: f32) -> Option<HitRecord> {
+        let direction = Vec3::new(0.0, 0.0, if z > 0.0 { -1.0 } else { 1.0 });
+        triangle.hit(Ray::new(Point3::new(x, y, z), direction), 0.001, f32::MAX, &HitRecord::new_def())
+    }
+
+    #[test]
+    fn hit_reports_distance_normal_and_uvs() {
+        let hit = hit_along_z(&right_triangle(), 0.5, 1.0, 3.0).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-6, "{}", hit.t);
+        assert!((hit.point - Point3::new(0.5, 1.0, 0.0)).length() < 1e-6, "{}", hit.point);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-6, "{}", hit.normal);
+        assert!(hit.front_face);
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.5).abs() < 1e-6, "{} {}", hit.u, hit.v);
+
+        let back = hit_along_z(&right_triangle(), 0.5, 1.0, -3.0).unwrap();
+        assert!(!back.front_face);
+        assert!((back.normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6, "{}", back.normal);
+    }
+
+    #[test]
+    fn hit_derives_dpdu_and_dpdv_from_the_uvs() {
+        let triangle = right_triangle().with_uvs([(0.0, 0.0), (0.0, 1.0), (0.5, 0.0)]);
+        let hit = hit_along_z(&triangle, 0.5, 1.0, 3.0).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.25).abs() < 1e-6, "{} {}", hit.u, hit.v);
+        assert!((hit.dpdu - Vec3::new(0.0, 4.0, 0.0)).length() < 1e-5, "{}", hit.dpdu);
+        assert!((hit.dpdv - Vec3::new(2.0, 0.0, 0.0)).length() < 1e-5, "{}", hit.dpdv);
+
+        let degenerate = right_triangle().with_uvs([(0.5, 0.5), (0.5, 0.5), (0.5, 0.5)]);
+        let hit = hit_along_z(&degenerate, 0.5, 1.0, 3.0).unwrap();
+        assert!((hit.dpdu.length() - 1.0).abs() < 1e-5, "{}", hit.dpdu);
+        assert!(hit.dpdu.dot(hit.normal).abs() < 1e-5, "{}", hit.dpdu);
+    }
+
+    #[test]
+    fn hit_includes_edges_and_vertices() {
+        let triangle = right_triangle();
+        for &(x, y) in &[(0.0, 0.0), (2.0, 0.0), (0.0, 2.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            assert!(hit_along_z(&triangle, x, y, 3.0).is_some(), "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn hit_misses_outside_the_triangle_and_the_ray_interval() {
+        let triangle = right_triangle();
+        for &(x, y) in &[(1.5, 1.5), (-0.1, 0.5), (0.5, -0.1), (3.0, 0.0)] {
+            assert!(hit_along_z(&triangle, x, y, 3.0).is_none(), "({}, {})", x, y);
+        }
+        let parallel = Ray::new(Point3::new(-1.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(triangle.hit(parallel, 0.001, f32::MAX, &HitRecord::new_def()).is_none());
+        let towards = Ray::new(Point3::new(0.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(triangle.hit(towards, 0.001, 2.5, &HitRecord::new_def()).is_none());
+        let away = Ray::new(Point3::new(0.5, 0.5, 3.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(triangle.hit(away, 0.001, f32::MAX, &HitRecord::new_def()).is_none());
+        let flat = Triangle::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 0.0), Point3::new(2.0, 2.0, 0.0), None);
+        assert!(hit_along_z(&flat, 1.0, 1.0, 3.0).is_none());
+    }
+
+    #[test]
+    fn displaced_normals_follow_the_height_gradient() {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let flat = Triangle::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), None);
+        let displacement = Displacement { height: Arc::new(Ramp), scale: 0.5, subdivisions: 8 };
+        let expected = Vec3::unit_vector(Vec3::new(-0.5, 1.0, 0.0));
+        let ray = Ray::new(Point3::new(0.3, 10.0, -0.2), Vec3::new(0.0, -1.0, 0.0));
+        let hit = flat.displaced(&displacement, [up, up, up]).iter()
+            .filter_map(|triangle| triangle.hit(ray, 0.001, f32::MAX, &HitRecord::new_def()))
+            .next()
+            .unwrap();
+        assert!((hit.point.y() - 0.15).abs() < TOLERANCE, "{}", hit.point);
+        assert!((hit.shading_normal - expected).length() < TOLERANCE, "{}", hit.shading_normal);
+        assert!((hit.normal - expected).length() < TOLERANCE, "{}", hit.normal);
+    }
+}
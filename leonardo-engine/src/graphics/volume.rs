@@ -0,0 +1,238 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::algebra::vec3::Point3;
+
+const BRICK_SIZE: usize = 8;
+const BRICK_VOLUME: usize = BRICK_SIZE * BRICK_SIZE * BRICK_SIZE;
+const MAX_SPARSE_OCCUPANCY: f32 = 0.5;
+
+type Bricks = Vec<Option<Box<[f32]>>>;
+
+enum Storage {
+    Dense(Vec<f32>),
+    Sparse {
+        bricks: [usize; 3],
+        data: Bricks,
+    },
+}
+
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    storage: Storage,
+    max_value: f32,
+}
+
+impl VoxelGrid {
+    pub fn new(resolution: [usize; 3], values: Vec<f32>) -> VoxelGrid {
+        let dense = VoxelGrid::dense(resolution, values);
+        let (bricks, data) = dense.bricks();
+        let occupied = data.iter().filter(|brick| brick.is_some()).count();
+        if (occupied as f32) < MAX_SPARSE_OCCUPANCY * data.len() as f32 {
+            return VoxelGrid {
+                storage: Storage::Sparse { bricks, data },
+                ..dense
+            };
+        }
+        dense
+    }
+    pub fn dense(resolution: [usize; 3], values: Vec<f32>) -> VoxelGrid {
+        assert_eq!(values.len(), resolution[0] * resolution[1] * resolution[2], "voxel count does not match the resolution");
+        let values: Vec<f32> = values.into_iter().map(|value| value.max(0.0)).collect();
+        let max_value = values.iter().fold(0.0_f32, |acc, &value| acc.max(value));
+        VoxelGrid {
+            resolution,
+            storage: Storage::Dense(values),
+            max_value,
+        }
+    }
+    pub fn sparse(resolution: [usize; 3], values: Vec<f32>) -> VoxelGrid {
+        VoxelGrid::dense(resolution, values).into_sparse()
+    }
+    fn into_sparse(self) -> VoxelGrid {
+        let (bricks, data) = self.bricks();
+        VoxelGrid {
+            storage: Storage::Sparse { bricks, data },
+            ..self
+        }
+    }
+    fn bricks(&self) -> ([usize; 3], Bricks) {
+        let bricks = brick_counts(self.resolution);
+        let data = (0..bricks[0] * bricks[1] * bricks[2])
+            .map(|index| self.brick(bricks, index))
+            .collect();
+        (bricks, data)
+    }
+    fn brick(&self, bricks: [usize; 3], index: usize) -> Option<Box<[f32]>> {
+        let origin = [
+            index % bricks[0] * BRICK_SIZE,
+            index / bricks[0] % bricks[1] * BRICK_SIZE,
+            index / (bricks[0] * bricks[1]) * BRICK_SIZE,
+        ];
+        let mut values = vec![0.0; BRICK_VOLUME];
+        let mut occupied = false;
+        for (offset, value) in values.iter_mut().enumerate() {
+            let x = origin[0] + offset % BRICK_SIZE;
+            let y = origin[1] + offset / BRICK_SIZE % BRICK_SIZE;
+            let z = origin[2] + offset / (BRICK_SIZE * BRICK_SIZE);
+            if x < self.resolution[0] && y < self.resolution[1] && z < self.resolution[2] {
+                *value = self.voxel(x, y, z);
+                occupied |= *value > 0.0;
+            }
+        }
+        if occupied { Some(values.into_boxed_slice()) } else { None }
+    }
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+    pub fn max_value(&self) -> f32 {
+        self.max_value
+    }
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.storage, Storage::Sparse { .. })
+    }
+    pub fn voxel(&self, x: usize, y: usize, z: usize) -> f32 {
+        match &self.storage {
+            Storage::Dense(values) => values[(z * self.resolution[1] + y) * self.resolution[0] + x],
+            Storage::Sparse { bricks, data } => {
+                let index = ((z / BRICK_SIZE) * bricks[1] + y / BRICK_SIZE) * bricks[0] + x / BRICK_SIZE;
+                match &data[index] {
+                    Some(brick) => {
+                        let offset = ((z % BRICK_SIZE) * BRICK_SIZE + y % BRICK_SIZE) * BRICK_SIZE + x % BRICK_SIZE;
+                        brick[offset]
+                    }
+                    None => 0.0
+                }
+            }
+        }
+    }
+    pub fn sample(&self, point: Point3) -> f32 {
+        if (0..3).any(|axis| !(0.0..=1.0).contains(&point[axis])) {
+            return 0.0;
+        }
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut weight = [0.0; 3];
+        for axis in 0..3 {
+            let coordinate = point[axis] * self.resolution[axis] as f32 - 0.5;
+            let floor = coordinate.floor();
+            let last = self.resolution[axis] as isize - 1;
+            lower[axis] = (floor as isize).clamp(0, last) as usize;
+            upper[axis] = (floor as isize + 1).clamp(0, last) as usize;
+            weight[axis] = coordinate - floor;
+        }
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let mut w = 1.0;
+            let mut index = [0; 3];
+            for axis in 0..3 {
+                if corner >> axis & 1 == 1 {
+                    index[axis] = upper[axis];
+                    w *= weight[axis];
+                } else {
+                    index[axis] = lower[axis];
+                    w *= 1.0 - weight[axis];
+                }
+            }
+            if w > 0.0 {
+                value += w * self.voxel(index[0], index[1], index[2]);
+            }
+        }
+        value
+    }
+}
+
+fn brick_counts(resolution: [usize; 3]) -> [usize; 3] {
+    [
+        resolution[0].div_ceil(BRICK_SIZE),
+        resolution[1].div_ceil(BRICK_SIZE),
+        resolution[2].div_ceil(BRICK_SIZE),
+    ]
+}
+
+pub struct GridVolume {
+    pub density: VoxelGrid,
+    pub temperature: Option<VoxelGrid>,
+}
+
+impl GridVolume {
+    pub fn new(density: VoxelGrid) -> GridVolume {
+        GridVolume {
+            density,
+            temperature: None,
+        }
+    }
+    pub fn with_temperature(mut self, temperature: VoxelGrid) -> GridVolume {
+        self.temperature = Some(temperature);
+        self
+    }
+    pub fn load<P: AsRef<Path>>(path: P, resolution: Option<[usize; 3]>) -> io::Result<GridVolume> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let (resolution, data) = if bytes.starts_with(b"VOL") {
+            parse_vol_header(path, &bytes)?
+        } else {
+            match resolution {
+                Some(resolution) => (resolution, &bytes[..]),
+                None => return Err(invalid_data(path, "a raw grid needs a resolution"))
+            }
+        };
+        let voxels = checked_product(path, &resolution)?;
+        if voxels == 0 {
+            return Err(invalid_data(path, "the grid resolution must be positive"));
+        }
+        let channel_size = checked_product(path, &[voxels, 4])?;
+        let channels = data.len() / channel_size;
+        if data.len() != channel_size * channels || !(1..=2).contains(&channels) {
+            return Err(invalid_data(path, &format!("expected {} or {} floats of grid data", voxels, 2 * voxels)));
+        }
+        let floats: Vec<f32> = data.chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        let channel = |c: usize| floats.iter().skip(c).step_by(channels).copied().collect::<Vec<f32>>();
+        let volume = GridVolume::new(VoxelGrid::new(resolution, channel(0)));
+        if channels == 2 {
+            return Ok(volume.with_temperature(VoxelGrid::new(resolution, channel(1))));
+        }
+        Ok(volume)
+    }
+}
+
+fn parse_vol_header<'a>(path: &Path, bytes: &'a [u8]) -> io::Result<([usize; 3], &'a [u8])> {
+    const HEADER_SIZE: usize = 48;
+    if bytes.len() < HEADER_SIZE || bytes[3] != 3 {
+        return Err(invalid_data(path, "unsupported VOL header"));
+    }
+    let int = |offset: usize| i32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+    if int(4) != 1 {
+        return Err(invalid_data(path, "only 32-bit float VOL grids are supported"));
+    }
+    let resolution = [int(8), int(12), int(16)];
+    if resolution.iter().any(|&n| n <= 0) {
+        return Err(invalid_data(path, "the grid resolution must be positive"));
+    }
+    let resolution = resolution.map(|n| n as usize);
+    let channels = int(20);
+    if !(1..=2).contains(&channels) {
+        return Err(invalid_data(path, "expected one or two channels of grid data"));
+    }
+    let expected = checked_product(path, &[resolution[0], resolution[1], resolution[2], channels as usize, 4])?;
+    if bytes.len() - HEADER_SIZE != expected {
+        return Err(invalid_data(path, "expected one or two channels of grid data"));
+    }
+    Ok((resolution, &bytes[HEADER_SIZE..]))
+}
+
+fn checked_product(path: &Path, factors: &[usize]) -> io::Result<usize> {
+    factors.iter()
+        .try_fold(1_usize, |product, &factor| product.checked_mul(factor))
+        .ok_or_else(|| invalid_data(path, "the grid resolution is too large"))
+}
+
+fn invalid_data(path: &Path, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message),
+    )
+}
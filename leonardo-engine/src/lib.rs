@@ -1,21 +1,31 @@
-use std::fs::File;
-use std::io::Write;
-use std::rc::Rc;
+use std::io;
+use std::ops::{Add, Mul};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
 use indicatif::ProgressBar;
 
-use crate::algebra::utility::{random_f32, random_in_range_f32};
-use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::algebra::utility::{mix_seed, random_f32, seed_rng};
+use crate::algebra::vec3::Color;
+use crate::graphics::background::Background;
+use crate::graphics::bvh::BvhNode;
 use crate::graphics::camera::Camera;
-use crate::graphics::hit::{HitRecord, Hittable, HittableStore};
-use crate::graphics::material::{Dielectric, Lambertian, Metal};
-use crate::graphics::ppm::write_color;
+use crate::graphics::hit::{HitRecord, Hittable};
+use crate::graphics::image::{Framebuffer, ImageFormat};
+use crate::graphics::light::LightList;
+use crate::graphics::material::Material;
 use crate::graphics::ray::Ray;
-use crate::graphics::sphere::Sphere;
+use crate::graphics::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::graphics::tonemap::DisplayTransform;
+use crate::scene::{ImageSettings, Scene};
 
-mod graphics;
-mod algebra;
+pub mod graphics;
+pub mod algebra;
+pub mod scene;
 
+const TILE_SIZE: i32 = 16;
 
 pub struct App {
     height: i32,
@@ -23,151 +33,313 @@ pub struct App {
     samples_per_pixel: i32,
     max_depth: i32,
     aspect_ratio: f32,
+    threads: usize,
+    seed: u64,
+    scene: Option<Scene>,
+    output: PathBuf,
+    format: ImageFormat,
+    display_transform: DisplayTransform,
+    spectral: bool,
 }
 
+struct Tile {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    if pdf <= 0.0 {
+        return 0.0;
+    }
+    let ratio = other_pdf / pdf;
+    1.0 / (1.0 + ratio * ratio)
+}
+
+trait Radiance: Copy + Add<Output = Self> + Mul<Output = Self> + Mul<f32, Output = Self> {}
+
+impl<S: Copy + Add<Output = S> + Mul<Output = S> + Mul<f32, Output = S>> Radiance for S {}
+
 impl App {
-    fn ray_color(&self, ray: Ray, world: &HittableStore, depth: i32) -> Color {
-        let hit_record = HitRecord::new_def();
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
+    fn sample_light<S: Radiance>(&self, ray: Ray, hit_record: &HitRecord, material: &dyn Material, world: &dyn Hittable, lights: &LightList, lift: &impl Fn(Color) -> S) -> Option<S> {
+        let direction = lights.random(hit_record.point);
+        let light_pdf = lights.pdf_value(hit_record.point, direction);
+        if light_pdf <= 0.0 || hit_record.leaks(direction) {
+            return None;
         }
-        return match world.hit(ray, 0.001, f32::MAX, &hit_record) {
-            Some(new_hit_record) => {
-                let scattered = Ray::new(
-                    Point3::new(0.0, 0.0, 0.0),
-                    Vec3::new(0.0, 0.0, 0.0),
-                );
-                let attenuation = Color::new(0.0, 0.0, 0.0);
-                return match new_hit_record.material.as_ref() {
-                    Some(material) => {
-                        let scatter = material.scatter(ray, &new_hit_record, attenuation, scattered);
-                        if scatter.is_scattered {
-                            return scatter.attenuation * self.ray_color(scatter.scattered, world, depth - 1);
-                        }
-                        return Color::new(0.0, 0.0, 0.0);
-                    }
-                    None => Color::new(0.0, 0.0, 0.0)
-                };
-            }
-            _ => {
-                let unit_direction = Vec3::unit_vector(ray.direction());
-                let t = 0.5 * (unit_direction.y() + 1.0);
-                Color::new(1.0, 1.0, 1.0) * (1.0 - t) + Color::new(0.5, 0.7, 1.0) * t
-            }
+        let f = material.eval(ray, hit_record, direction);
+        if f.length_square() <= 0.0 {
+            return None;
+        }
+        let shadow_ray = Ray::with_time(hit_record.point, direction, ray.time());
+        let (emitted, t) = match world.hit_surface(shadow_ray, 0.001, f32::MAX, &HitRecord::new_def()) {
+            Some(light_record) => (light_record.material.as_ref()?.emitted(shadow_ray, &light_record), light_record.t),
+            None => (lights.environment()?.color(shadow_ray), f32::MAX)
         };
+        if emitted.length_square() <= 0.0 {
+            return None;
+        }
+        let transmittance = world.transmittance(shadow_ray, 0.001, t);
+        let weight = power_heuristic(light_pdf, material.pdf(ray, hit_record, direction));
+        Some(lift(f) * lift(emitted) * (transmittance * weight / light_pdf))
     }
 
-    fn random_scene(&self) -> HittableStore {
-        let rnd_gen = rand::thread_rng();
-        let mut world = HittableStore::new();
-        world.store(Box::new(
-            Sphere::new(
-                Point3::new(0.0, -1000.0, 0.0),
-                1000.0,
-                Some(Rc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0)))))));
-        for a in -11..11 {
-            for b in -11..11 {
-                let choose_mat = random_f32(rnd_gen);
-                let center = Point3::new(
-                    (random_f32(rnd_gen) * 0.9) + a as f32,
-                    0.2,
-                    (random_f32(rnd_gen) * 0.9) + b as f32,
-                );
-                if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                    if choose_mat < 0.8 {
-                        let albedo = Color::random() * Color::random();
-                        world.store(Box::new(
-                            Sphere::new(
-                                center,
-                                0.2,
-                                Some(Rc::new(
-                                    Lambertian::new(albedo)
-                                )),
-                            )
-                        ))
-                    } else if choose_mat < 0.95 {
-                        let albedo = Color::random_in_range(0.5, 1.0);
-                        let fuzz = random_in_range_f32(rnd_gen, 0.0, 0.5);
-                        world.store(Box::new(
-                            Sphere::new(
-                                center,
-                                0.2,
-                                Some(Rc::new(
-                                    Metal::new(albedo, fuzz)
-                                )),
-                            )
-                        ))
+    fn ray_color<S: Radiance>(&self, ray: Ray, world: &dyn Hittable, lights: &LightList, background: &dyn Background, depth: i32, lift: &impl Fn(Color) -> S) -> (S, bool) {
+        let hit_record = HitRecord::new_def();
+        let mut color = lift(Color::new(0.0, 0.0, 0.0));
+        let mut throughput = lift(Color::new(1.0, 1.0, 1.0));
+        let mut dispersed = false;
+        let mut ray = ray;
+        let mut specular_bounce = true;
+        let mut bsdf_pdf = 0.0;
+        for _ in 0..depth {
+            let new_hit_record = match world.hit(ray, 0.001, f32::MAX, &hit_record) {
+                Some(new_hit_record) => new_hit_record,
+                None => {
+                    let emitted = lift(background.color(ray));
+                    if specular_bounce || lights.environment().is_none() {
+                        color = color + throughput * emitted;
                     } else {
-                        world.store(Box::new(
-                            Sphere::new(
-                                center,
-                                0.2,
-                                Some(Rc::new(
-                                    Dielectric::new(1.5)
-                                )),
-                            )
-                        ))
+                        let light_pdf = lights.pdf_value(ray.origin(), ray.direction());
+                        color = color + throughput * emitted * power_heuristic(bsdf_pdf, light_pdf);
                     }
+                    break;
                 }
+            };
+            let material = match new_hit_record.material.as_ref() {
+                Some(material) => material.as_ref(),
+                None => break
+            };
+            let emitted = material.emitted(ray, &new_hit_record);
+            if specular_bounce {
+                color = color + throughput * lift(emitted);
+            } else if emitted.length_square() > 0.0 {
+                let light_pdf = lights.pdf_value(ray.origin(), ray.direction());
+                color = color + throughput * lift(emitted) * power_heuristic(bsdf_pdf, light_pdf);
+            }
+            let scatter = material.sample(ray, &new_hit_record);
+            if !scatter.is_scattered {
+                break;
             }
+            if !scatter.is_specular && !lights.is_empty() {
+                if let Some(direct) = self.sample_light(ray, &new_hit_record, material, world, lights, lift) {
+                    color = color + throughput * direct;
+                }
+            }
+            if new_hit_record.leaks(scatter.scattered.direction()) {
+                break;
+            }
+            dispersed |= material.is_dispersive();
+            throughput = throughput * lift(scatter.attenuation);
+            specular_bounce = scatter.is_specular;
+            bsdf_pdf = scatter.pdf;
+            ray = scatter.scattered.with_wavelength(ray.wavelength());
         }
-
-        world.store(
-            Box::new(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0,
-                                 Some(Rc::new(Dielectric::new(1.5))))));
-        world.store(
-            Box::new(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0,
-                                 Some(Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)))))));
-        world.store(
-            Box::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0,
-                                 Some(Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0))))));
-        world
+        (color, dispersed)
     }
+
     pub fn new(aspect_ratio: f32, width: i32, samples_per_pixel: i32, max_depth: i32) -> App {
         let height = (width as f32 / aspect_ratio) as i32;
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
         App {
             height,
             width,
             samples_per_pixel,
             max_depth,
             aspect_ratio,
+            threads,
+            seed: 0,
+            scene: None,
+            output: PathBuf::from("ray.ppm"),
+            format: ImageFormat::PpmBinary,
+            display_transform: DisplayTransform::default(),
+            spectral: false,
         }
     }
-    pub fn run(&self) -> std::io::Result<()> {
-        let mut content = String::new();
-        content.push_str(format!("P3\n{} {}\n255\n", self.width, self.height).as_str());
-        let mut world = self.random_scene();
-
-        let camera = Camera::new(
-            Point3::new(13.0, 2.0, 3.0),
-            Point3::new(0.0, 0.0, 0.0),
-            Vec3::new(0.0, 1.0, 0.0),
-            20.0,
-            self.aspect_ratio);
-
-        let rnd_generator = rand::thread_rng();
-
+    pub fn from_scene(scene: Scene) -> App {
+        let image = scene.image.clone();
+        App::new(image.aspect_ratio, image.width, image.samples_per_pixel, image.max_depth)
+            .with_spectral(image.spectral)
+            .with_scene(scene)
+    }
+    pub fn with_scene(mut self, scene: Scene) -> App {
+        self.scene = Some(scene);
+        self
+    }
+    fn image_settings(&self) -> ImageSettings {
+        ImageSettings {
+            aspect_ratio: self.aspect_ratio,
+            width: self.width,
+            samples_per_pixel: self.samples_per_pixel,
+            max_depth: self.max_depth,
+            spectral: self.spectral,
+        }
+    }
+    pub fn with_resolution(mut self, width: i32, height: i32) -> App {
+        self.width = width;
+        self.height = height;
+        self.aspect_ratio = width as f32 / height as f32;
+        self
+    }
+    pub fn with_samples_per_pixel(mut self, samples_per_pixel: i32) -> App {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+    pub fn with_max_depth(mut self, max_depth: i32) -> App {
+        self.max_depth = max_depth;
+        self
+    }
+    pub fn with_output<P: AsRef<Path>>(mut self, output: P) -> App {
+        self.output = output.as_ref().to_path_buf();
+        if let Some(format) = ImageFormat::from_path(&self.output) {
+            self.format = format;
+        }
+        self
+    }
+    pub fn with_format(mut self, format: ImageFormat) -> App {
+        self.format = format;
+        self
+    }
+    pub fn with_display_transform(mut self, display_transform: DisplayTransform) -> App {
+        self.display_transform = display_transform;
+        self
+    }
+    pub fn with_threads(mut self, threads: usize) -> App {
+        self.threads = threads.max(1);
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> App {
+        self.seed = seed;
+        self
+    }
+    pub fn with_spectral(mut self, spectral: bool) -> App {
+        self.spectral = spectral;
+        self
+    }
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y0 in (0..self.height).step_by(TILE_SIZE as usize) {
+            for x0 in (0..self.width).step_by(TILE_SIZE as usize) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + TILE_SIZE).min(self.width),
+                    y1: (y0 + TILE_SIZE).min(self.height),
+                });
+            }
+        }
+        tiles
+    }
+    fn render_pixel(&self, i: i32, j: i32, world: &dyn Hittable, lights: &LightList, background: &dyn Background, camera: &Camera) -> Color {
+        seed_rng(mix_seed(self.seed, (j * self.width + i) as u64));
+        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+        for _s in 0..self.samples_per_pixel {
+            let u = (i as f32 + random_f32()) / (self.width - 1) as f32;
+            let v = (j as f32 + random_f32()) / (self.height - 1) as f32;
+            let r = camera.get_ray(u, v);
+            if self.spectral {
+                let wavelengths = SampledWavelengths::sample_visible(random_f32());
+                let lift = |color| SampledSpectrum::from_rgb(color, &wavelengths);
+                let r = r.with_wavelength(Some(wavelengths.hero()));
+                let (radiance, dispersed) = self.ray_color(r, world, lights, background, self.max_depth, &lift);
+                let wavelengths = if dispersed { wavelengths.terminate_secondary() } else { wavelengths };
+                pixel_color = pixel_color + wavelengths.to_rgb(radiance);
+            } else {
+                let (color, _) = self.ray_color(r, world, lights, background, self.max_depth, &|color| color);
+                pixel_color = pixel_color + color;
+            }
+        }
+        pixel_color / self.samples_per_pixel as f32
+    }
+    fn render(&self, world: &dyn Hittable, lights: &LightList, background: &dyn Background, camera: &Camera) -> Framebuffer {
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
         let progress_bar = ProgressBar::new((self.width * self.height) as u64);
+        let mut framebuffer = Framebuffer::new(self.width as usize, self.height as usize);
+        let (sender, receiver) = mpsc::channel::<(usize, Vec<Color>)>();
 
-        for j in (0..self.height).rev() {
-            for i in 0..self.width {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                for _s in 0..self.samples_per_pixel {
-                    let u = (i as f32 + random_f32(rnd_generator)) / (self.width - 1) as f32;
-                    let v = (j as f32 + random_f32(rnd_generator)) / (self.height - 1) as f32;
-                    let r = camera.get_ray(u, v);
-                    pixel_color = pixel_color + self.ray_color(r, &world, self.max_depth);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(tiles.len()) {
+                let sender = sender.clone();
+                let tiles = &tiles;
+                let next_tile = &next_tile;
+                let progress_bar = &progress_bar;
+                scope.spawn(move || loop {
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    if index >= tiles.len() {
+                        break;
+                    }
+                    let tile = &tiles[index];
+                    let mut pixels = Vec::with_capacity(((tile.x1 - tile.x0) * (tile.y1 - tile.y0)) as usize);
+                    for j in tile.y0..tile.y1 {
+                        for i in tile.x0..tile.x1 {
+                            pixels.push(self.render_pixel(i, j, world, lights, background, camera));
+                        }
+                        progress_bar.inc((tile.x1 - tile.x0) as u64);
+                    }
+                    if sender.send((index, pixels)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+            for (index, pixels) in receiver.iter() {
+                let tile = &tiles[index];
+                let tile_width = (tile.x1 - tile.x0) as usize;
+                for (k, pixel) in pixels.into_iter().enumerate() {
+                    let i = tile.x0 as usize + k % tile_width;
+                    let j = tile.y0 as usize + k / tile_width;
+                    framebuffer.set(i, self.height as usize - 1 - j, pixel);
                 }
-                content.push_str(write_color(pixel_color, self.samples_per_pixel as f32).as_str());
-                progress_bar.inc(1);
             }
-        }
+        });
         progress_bar.finish();
-        let mut file = match File::create("ray.ppm") {
-            Ok(file) => file,
-            Err(err) => panic!("Could not create file {:?}", err)
+        framebuffer
+    }
+    pub fn render_image(&self) -> io::Result<Framebuffer> {
+        seed_rng(self.seed);
+        let mut scene = match &self.scene {
+            Some(scene) => scene.clone(),
+            None => Scene::random_spheres()
         };
-        file.write_all(content.as_bytes())?;
-        Ok(())
+        scene.image = self.image_settings();
+        let (world, mut lights, camera) = scene.build()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let background = scene.background()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        lights.set_environment(background.clone());
+        let (shutter_open, shutter_close) = camera.shutter();
+        let (bounded, mut world) = world.split_unbounded(shutter_open, shutter_close);
+        world.store(Box::new(BvhNode::new(bounded, shutter_open, shutter_close)));
+
+        Ok(self.render(&world, &lights, background.as_ref(), &camera))
     }
-}
\ No newline at end of file
+    pub fn run(&self) -> io::Result<()> {
+        self.render_image()?.save(&self.output, self.format, self.display_transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_with_threads(threads: usize) -> Framebuffer {
+        App::new(1.0, 24, 4, 8)
+            .with_scene(Scene::cornell_box())
+            .with_threads(threads)
+            .with_seed(7)
+            .render_image()
+            .unwrap()
+    }
+
+    #[test]
+    fn thread_count_does_not_change_the_image() {
+        let single = render_with_threads(1);
+        let parallel = render_with_threads(4);
+        assert_eq!(single.pixels().len(), parallel.pixels().len());
+        for (a, b) in single.pixels().iter().zip(parallel.pixels()) {
+            assert!(a.x() == b.x() && a.y() == b.y() && a.z() == b.z(), "{} != {}", a, b);
+        }
+    }
+}
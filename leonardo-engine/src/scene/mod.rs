@@ -0,0 +1,944 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::algebra::transform::Transform;
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::aabb::Aabb;
+use crate::graphics::background::{Background, SkyGradient, SolidBackground};
+use crate::graphics::environment::EnvironmentMap;
+use crate::graphics::camera::{Camera, Focus};
+use crate::graphics::cone::Cone;
+use crate::graphics::cuboid::Cuboid;
+use crate::graphics::cylinder::Cylinder;
+use crate::graphics::disk::Disk;
+use crate::graphics::hit::{Hittable, HittableStore};
+use crate::graphics::light::LightList;
+use crate::graphics::medium::{ConstantMedium, GridMedium};
+use crate::graphics::material::{
+    Conductor, Dielectric, DiffuseLight, HenyeyGreenstein, Ior, Isotropic, Lambertian, Material, Metal, RoughDielectric,
+};
+use crate::graphics::mesh::Mesh;
+use crate::graphics::moving_sphere::MovingSphere;
+use crate::graphics::plane::Plane;
+use crate::graphics::principled::Principled;
+use crate::graphics::quad::Quad;
+use crate::graphics::quadric::{Quadric, QuadricCoefficients};
+use crate::graphics::rect::Rect;
+use crate::graphics::shading::{Perturbed, ShadingMap};
+use crate::graphics::sky::PhysicalSky;
+use crate::graphics::sphere::Sphere;
+use crate::graphics::texture::{
+    Checker, Filter, ImageTexture, MarbleTexture, NoiseKind, NoiseTexture, SolidColor, Texture, UvChecker, WrapMode,
+};
+use crate::graphics::torus::Torus;
+use crate::graphics::transformed::Transformed;
+use crate::graphics::triangle::{Displacement, Triangle};
+use crate::graphics::volume::GridVolume;
+
+mod parser;
+mod presets;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Invalid(String),
+    Serialize(String),
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SceneError::Parse { line, column, message } => write!(f, "line {}, column {}: {}", line, column, message),
+            SceneError::Invalid(message) => write!(f, "{}", message),
+            SceneError::Serialize(message) => write!(f, "could not serialize scene: {}", message),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+pub(crate) fn serialize_f32<S: Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(value.to_string().parse::<f64>().unwrap_or(*value as f64))
+}
+
+fn serialize_optional_f32<S: Serializer>(value: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serialize_f32(value, serializer),
+        None => serializer.serialize_none()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageSettings {
+    #[serde(serialize_with = "serialize_f32")]
+    pub aspect_ratio: f32,
+    pub width: i32,
+    pub samples_per_pixel: i32,
+    pub max_depth: i32,
+    #[serde(skip_serializing_if = "is_false")]
+    pub spectral: bool,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        ImageSettings {
+            aspect_ratio: 16.0 / 9.0,
+            width: 384,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            spectral: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraSettings {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    #[serde(default = "default_vup")]
+    pub vup: Vec3,
+    #[serde(serialize_with = "serialize_f32")]
+    pub vfov: f32,
+    #[serde(default, skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub aperture: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "serialize_optional_f32")]
+    pub focus_distance: Option<f32>,
+    #[serde(default, skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub shutter_open: f32,
+    #[serde(default, skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+    pub shutter_close: f32,
+}
+
+pub(crate) fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn default_vup() -> Vec3 {
+    Vec3::new(0.0, 1.0, 0.0)
+}
+
+fn default_sky_bottom() -> Color {
+    Color::new(1.0, 1.0, 1.0)
+}
+
+fn default_sky_top() -> Color {
+    Color::new(0.5, 0.7, 1.0)
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackgroundSpec {
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    Solid {
+        color: Color,
+    },
+    Environment {
+        path: PathBuf,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        rotation: f32,
+        #[serde(serialize_with = "serialize_f32")]
+        intensity: f32,
+    },
+    CubeMap {
+        faces: [PathBuf; 6],
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        rotation: f32,
+        #[serde(serialize_with = "serialize_f32")]
+        intensity: f32,
+    },
+    Sky {
+        sun_direction: Vec3,
+        #[serde(serialize_with = "serialize_f32")]
+        turbidity: f32,
+        ground_albedo: Color,
+        #[serde(serialize_with = "serialize_f32")]
+        sun_diameter: f32,
+        #[serde(serialize_with = "serialize_f32")]
+        intensity: f32,
+    },
+}
+
+impl Default for BackgroundSpec {
+    fn default() -> Self {
+        BackgroundSpec::Gradient {
+            bottom: default_sky_bottom(),
+            top: default_sky_top(),
+        }
+    }
+}
+
+impl BackgroundSpec {
+    fn build(&self, scene: &Scene) -> Result<Arc<dyn Background>, SceneError> {
+        let load_error = |path: PathBuf| move |err: io::Error| match err.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => SceneError::Invalid(err.to_string()),
+            _ => SceneError::Io(path, err)
+        };
+        Ok(match self {
+            BackgroundSpec::Gradient { bottom, top } => Arc::new(SkyGradient::new(*bottom, *top)),
+            BackgroundSpec::Solid { color } => Arc::new(SolidBackground::new(*color)),
+            BackgroundSpec::Environment { path, rotation, intensity } => {
+                let path = scene.resolve(path);
+                let environment = EnvironmentMap::load(&path).map_err(load_error(path))?;
+                Arc::new(environment.with_rotation(*rotation).with_intensity(*intensity))
+            }
+            BackgroundSpec::CubeMap { faces, rotation, intensity } => {
+                let faces = faces.iter()
+                    .map(|path| {
+                        let path = scene.resolve(path);
+                        ImageTexture::load(&path).map_err(load_error(path))
+                    })
+                    .collect::<Result<Vec<ImageTexture>, SceneError>>()?;
+                let environment = EnvironmentMap::cube(faces).map_err(|err| SceneError::Invalid(err.to_string()))?;
+                Arc::new(environment.with_rotation(*rotation).with_intensity(*intensity))
+            }
+            BackgroundSpec::Sky { sun_direction, turbidity, ground_albedo, sun_diameter, intensity } => {
+                Arc::new(PhysicalSky::new(*sun_direction, *turbidity, *ground_albedo, *sun_diameter).with_intensity(*intensity))
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TextureSpec {
+    Solid {
+        color: Color,
+    },
+    Checker {
+        odd: Color,
+        even: Color,
+        #[serde(serialize_with = "serialize_f32")]
+        scale: f32,
+    },
+    UvChecker {
+        odd: Color,
+        even: Color,
+        #[serde(serialize_with = "serialize_f32")]
+        scale: f32,
+    },
+    Image {
+        path: PathBuf,
+        wrap: WrapMode,
+        filter: Filter,
+        #[serde(skip_serializing_if = "is_false")]
+        linear: bool,
+    },
+    Noise {
+        noise: NoiseKind,
+        #[serde(serialize_with = "serialize_f32")]
+        scale: f32,
+        octaves: u32,
+        seed: u64,
+        low: Color,
+        high: Color,
+    },
+    Marble {
+        #[serde(serialize_with = "serialize_f32")]
+        scale: f32,
+        octaves: u32,
+        seed: u64,
+        low: Color,
+        high: Color,
+    },
+}
+
+impl TextureSpec {
+    fn build(&self, scene: &Scene) -> Result<Arc<dyn Texture>, SceneError> {
+        Ok(match self {
+            TextureSpec::Solid { color } => Arc::new(SolidColor::new(*color)),
+            TextureSpec::Checker { odd, even, scale } => Arc::new(Checker::from_colors(*odd, *even, *scale)),
+            TextureSpec::UvChecker { odd, even, scale } => Arc::new(UvChecker::from_colors(*odd, *even, *scale)),
+            TextureSpec::Image { path, wrap, filter, linear } => {
+                let path = scene.resolve(path);
+                let image = if *linear { ImageTexture::load_linear(&path) } else { ImageTexture::load(&path) };
+                let image = image.map_err(|err| match err.kind() {
+                    io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => SceneError::Invalid(err.to_string()),
+                    _ => SceneError::Io(path.clone(), err)
+                })?;
+                Arc::new(image.with_wrap(*wrap).with_filter(*filter))
+            }
+            TextureSpec::Noise { noise, scale, octaves, seed, low, high } => {
+                Arc::new(NoiseTexture::new(*noise, *scale, *octaves, *seed).with_colors(*low, *high))
+            }
+            TextureSpec::Marble { scale, octaves, seed, low, high } => {
+                Arc::new(MarbleTexture::new(*scale, *octaves, *seed).with_colors(*low, *high))
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum ColorSource {
+    Color(Color),
+    Value(#[serde(serialize_with = "serialize_f32")] f32),
+    Texture(String),
+}
+
+impl From<Color> for ColorSource {
+    fn from(color: Color) -> Self {
+        ColorSource::Color(color)
+    }
+}
+
+impl ColorSource {
+    fn build(&self, textures: &HashMap<&str, Arc<dyn Texture>>) -> Result<Arc<dyn Texture>, SceneError> {
+        match self {
+            ColorSource::Color(color) => Ok(Arc::new(SolidColor::new(*color))),
+            ColorSource::Value(value) => Ok(Arc::new(SolidColor::new(Color::new(*value, *value, *value)))),
+            ColorSource::Texture(name) => textures.get(name.as_str())
+                .cloned()
+                .ok_or_else(|| SceneError::Invalid(format!("unknown texture `{}`", name)))
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaterialSpec {
+    Lambertian {
+        albedo: ColorSource,
+    },
+    Metal {
+        albedo: ColorSource,
+        #[serde(serialize_with = "serialize_f32")]
+        fuzz: f32,
+    },
+    Dielectric {
+        #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_optional_f32")]
+        ref_idx: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dispersion: Option<DispersionSpec>,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        roughness: f32,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        anisotropy: f32,
+    },
+    Conductor {
+        eta: Color,
+        k: Color,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        roughness: f32,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        anisotropy: f32,
+    },
+    DiffuseLight {
+        emit: ColorSource,
+        #[serde(skip_serializing_if = "is_false")]
+        two_sided: bool,
+    },
+    Isotropic {
+        albedo: ColorSource,
+    },
+    HenyeyGreenstein {
+        albedo: ColorSource,
+        #[serde(serialize_with = "serialize_f32")]
+        anisotropy: f32,
+    },
+    Principled {
+        base_color: ColorSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        metallic: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        roughness: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        specular: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sheen: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        clearcoat: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        clearcoat_roughness: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        transmission: Option<ColorSource>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        emit: Option<ColorSource>,
+        #[serde(serialize_with = "serialize_f32")]
+        ior: f32,
+        #[serde(skip_serializing_if = "is_zero", serialize_with = "serialize_f32")]
+        anisotropy: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum DispersionSpec {
+    Cauchy {
+        #[serde(serialize_with = "serialize_f32")]
+        a: f32,
+        #[serde(serialize_with = "serialize_f32")]
+        b: f32,
+    },
+    Sellmeier {
+        b: Vec3,
+        c: Vec3,
+    },
+}
+
+impl DispersionSpec {
+    fn ior(&self) -> Ior {
+        match *self {
+            DispersionSpec::Cauchy { a, b } => Ior::Cauchy { a, b },
+            DispersionSpec::Sellmeier { b, c } => Ior::Sellmeier {
+                b: [b.x(), b.y(), b.z()],
+                c: [c.x(), c.y(), c.z()],
+            },
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl MaterialSpec {
+    fn build(&self, textures: &HashMap<&str, Arc<dyn Texture>>) -> Result<Arc<dyn Material>, SceneError> {
+        Ok(match self {
+            MaterialSpec::Lambertian { albedo } => Arc::new(Lambertian::textured(albedo.build(textures)?)),
+            MaterialSpec::Metal { albedo, fuzz } => Arc::new(Metal::textured(albedo.build(textures)?, *fuzz)),
+            MaterialSpec::Dielectric { ref_idx, dispersion, roughness, anisotropy } => {
+                let ior = match (dispersion, ref_idx) {
+                    (Some(dispersion), _) => dispersion.ior(),
+                    (None, Some(ref_idx)) => Ior::Constant(*ref_idx),
+                    (None, None) => return Err(SceneError::Invalid("dielectric requires `ref_idx` or `dispersion`".to_string())),
+                };
+                if *roughness > 0.0 {
+                    Arc::new(RoughDielectric::dispersive(ior, *roughness).with_anisotropy(*anisotropy))
+                } else {
+                    Arc::new(Dielectric::dispersive(ior))
+                }
+            }
+            MaterialSpec::Conductor { eta, k, roughness, anisotropy } => {
+                Arc::new(Conductor::new(*eta, *k, *roughness).with_anisotropy(*anisotropy))
+            }
+            MaterialSpec::DiffuseLight { emit, two_sided } => {
+                Arc::new(DiffuseLight::textured(emit.build(textures)?).with_two_sided(*two_sided))
+            }
+            MaterialSpec::Isotropic { albedo } => Arc::new(Isotropic::textured(albedo.build(textures)?)),
+            MaterialSpec::HenyeyGreenstein { albedo, anisotropy } => {
+                Arc::new(HenyeyGreenstein::textured(albedo.build(textures)?, *anisotropy))
+            }
+            MaterialSpec::Principled {
+                base_color,
+                metallic,
+                roughness,
+                specular,
+                sheen,
+                clearcoat,
+                clearcoat_roughness,
+                transmission,
+                emit,
+                ior,
+                anisotropy,
+            } => {
+                let mut material = Principled::textured(base_color.build(textures)?)
+                    .with_ior(*ior)
+                    .with_anisotropy(*anisotropy);
+                let layers = [
+                    (metallic, Principled::with_metallic as fn(_, _) -> _),
+                    (roughness, Principled::with_roughness),
+                    (specular, Principled::with_specular),
+                    (sheen, Principled::with_sheen),
+                    (clearcoat, Principled::with_clearcoat),
+                    (clearcoat_roughness, Principled::with_clearcoat_roughness),
+                    (transmission, Principled::with_transmission),
+                    (emit, Principled::with_emission),
+                ];
+                for (source, with) in layers {
+                    if let Some(source) = source {
+                        material = with(material, source.build(textures)?);
+                    }
+                }
+                Arc::new(material)
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    #[serde(serialize_with = "serialize_f32")]
+    pub time: f32,
+    pub center: Point3,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformSpec {
+    Translate(Vec3),
+    Scale(Vec3),
+    RotateX(#[serde(serialize_with = "serialize_f32")] f32),
+    RotateY(#[serde(serialize_with = "serialize_f32")] f32),
+    RotateZ(#[serde(serialize_with = "serialize_f32")] f32),
+    Rotate {
+        axis: Vec3,
+        #[serde(serialize_with = "serialize_f32")]
+        angle: f32,
+    },
+}
+
+impl TransformSpec {
+    fn build(&self) -> Transform {
+        match self {
+            TransformSpec::Translate(offset) => Transform::translate(*offset),
+            TransformSpec::Scale(factors) => Transform::scale(*factors),
+            TransformSpec::RotateX(angle) => Transform::rotate_x(*angle),
+            TransformSpec::RotateY(angle) => Transform::rotate_y(*angle),
+            TransformSpec::RotateZ(angle) => Transform::rotate_z(*angle),
+            TransformSpec::Rotate { axis, angle } => Transform::rotate(*axis, *angle),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeSpec {
+    Sphere {
+        center: Point3,
+        #[serde(serialize_with = "serialize_f32")]
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        keyframes: Vec<Keyframe>,
+        #[serde(serialize_with = "serialize_f32")]
+        radius: f32,
+        material: String,
+    },
+    Triangle {
+        vertices: [Point3; 3],
+        material: String,
+    },
+    Plane {
+        point: Point3,
+        normal: Vec3,
+        material: String,
+    },
+    Rect {
+        min: Point3,
+        max: Point3,
+        #[serde(skip_serializing_if = "is_false")]
+        flip: bool,
+        material: String,
+    },
+    Quad {
+        corner: Point3,
+        u: Vec3,
+        v: Vec3,
+        material: String,
+    },
+    Disk {
+        center: Point3,
+        normal: Vec3,
+        #[serde(serialize_with = "serialize_f32")]
+        radius: f32,
+        material: String,
+    },
+    Box {
+        min: Point3,
+        max: Point3,
+        material: String,
+    },
+    Cylinder {
+        base: Point3,
+        top: Point3,
+        #[serde(serialize_with = "serialize_f32")]
+        radius: f32,
+        capped: bool,
+        material: String,
+    },
+    Cone {
+        base: Point3,
+        apex: Point3,
+        #[serde(serialize_with = "serialize_f32")]
+        radius: f32,
+        capped: bool,
+        material: String,
+    },
+    Torus {
+        center: Point3,
+        normal: Vec3,
+        #[serde(serialize_with = "serialize_f32")]
+        major_radius: f32,
+        #[serde(serialize_with = "serialize_f32")]
+        minor_radius: f32,
+        material: String,
+    },
+    Quadric {
+        coefficients: QuadricCoefficients,
+        min: Point3,
+        max: Point3,
+        material: String,
+    },
+    Mesh {
+        path: PathBuf,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        displacement: Option<DisplacementSpec>,
+    },
+    Volume {
+        path: PathBuf,
+        min: Point3,
+        max: Point3,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        resolution: Option<[usize; 3]>,
+        #[serde(serialize_with = "serialize_f32")]
+        emission: f32,
+        material: String,
+    },
+}
+
+impl ShapeSpec {
+    pub fn material(&self) -> Option<&str> {
+        match self {
+            ShapeSpec::Sphere { material, .. }
+            | ShapeSpec::MovingSphere { material, .. }
+            | ShapeSpec::Triangle { material, .. }
+            | ShapeSpec::Plane { material, .. }
+            | ShapeSpec::Rect { material, .. }
+            | ShapeSpec::Quad { material, .. }
+            | ShapeSpec::Disk { material, .. }
+            | ShapeSpec::Box { material, .. }
+            | ShapeSpec::Cylinder { material, .. }
+            | ShapeSpec::Cone { material, .. }
+            | ShapeSpec::Torus { material, .. }
+            | ShapeSpec::Quadric { material, .. }
+            | ShapeSpec::Volume { material, .. } => Some(material),
+            ShapeSpec::Mesh { material, .. } => material.as_deref(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DisplacementSpec {
+    pub texture: String,
+    #[serde(serialize_with = "serialize_f32")]
+    pub scale: f32,
+    pub subdivisions: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NormalMapSpec {
+    pub texture: String,
+    #[serde(serialize_with = "serialize_f32")]
+    pub strength: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BumpMapSpec {
+    pub texture: String,
+    #[serde(serialize_with = "serialize_f32")]
+    pub scale: f32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ObjectSpec {
+    #[serde(flatten)]
+    pub shape: ShapeSpec,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_optional_f32")]
+    pub density: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normal_map: Option<NormalMapSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_map: Option<BumpMapSpec>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transform: Vec<TransformSpec>,
+}
+
+impl ObjectSpec {
+    pub fn new(shape: ShapeSpec) -> ObjectSpec {
+        ObjectSpec {
+            shape,
+            density: None,
+            normal_map: None,
+            bump_map: None,
+            transform: Vec::new(),
+        }
+    }
+    pub fn with_density(mut self, density: f32) -> ObjectSpec {
+        self.density = Some(density);
+        self
+    }
+    pub fn with_transform(mut self, transform: TransformSpec) -> ObjectSpec {
+        self.transform.push(transform);
+        self
+    }
+    fn shading(&self, textures: &HashMap<&str, Arc<dyn Texture>>) -> Result<Option<ShadingMap>, SceneError> {
+        let texture = |name: &str| textures.get(name)
+            .cloned()
+            .ok_or_else(|| SceneError::Invalid(format!("unknown texture `{}`", name)));
+        Ok(match (&self.normal_map, &self.bump_map) {
+            (Some(map), _) => Some(ShadingMap::Normal { texture: texture(&map.texture)?, strength: map.strength }),
+            (None, Some(map)) => Some(ShadingMap::Bump { height: texture(&map.texture)?, scale: map.scale }),
+            (None, None) => None
+        })
+    }
+    fn transform(&self) -> Option<Transform> {
+        if self.transform.is_empty() {
+            return None;
+        }
+        Some(self.transform.iter().fold(Transform::identity(), |acc, spec| acc.then(spec.build())))
+    }
+}
+
+impl From<ShapeSpec> for ObjectSpec {
+    fn from(shape: ShapeSpec) -> Self {
+        ObjectSpec::new(shape)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Scene {
+    pub image: ImageSettings,
+    pub camera: CameraSettings,
+    pub background: BackgroundSpec,
+    pub textures: BTreeMap<String, TextureSpec>,
+    pub materials: BTreeMap<String, MaterialSpec>,
+    pub objects: Vec<ObjectSpec>,
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+}
+
+impl Scene {
+    pub fn new(image: ImageSettings, camera: CameraSettings) -> Scene {
+        Scene {
+            image,
+            camera,
+            background: BackgroundSpec::default(),
+            textures: BTreeMap::new(),
+            materials: BTreeMap::new(),
+            objects: Vec::new(),
+            base_dir: None,
+        }
+    }
+    pub fn parse(source: &str) -> Result<Scene, SceneError> {
+        parser::parse(source)
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| SceneError::Io(path.to_path_buf(), err))?;
+        let mut scene = Scene::parse(&source)?;
+        scene.base_dir = path.parent().map(Path::to_path_buf);
+        Ok(scene)
+    }
+    pub fn random_spheres() -> Scene {
+        presets::random_spheres()
+    }
+    pub fn cornell_box() -> Scene {
+        presets::cornell_box()
+    }
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        toml::to_string(self).map_err(|err| SceneError::Serialize(err.to_string()))
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_toml()?)
+            .map_err(|err| SceneError::Io(path.to_path_buf(), err))
+    }
+    pub fn camera(&self) -> Camera {
+        Camera::new(
+            self.camera.look_from,
+            self.camera.look_at,
+            self.camera.vup,
+            self.camera.vfov,
+            self.image.aspect_ratio,
+        )
+            .with_aperture(self.camera.aperture)
+            .with_shutter(self.camera.shutter_open, self.camera.shutter_close)
+            .with_focus(match self.camera.focus_distance {
+                Some(distance) => Focus::Distance(distance),
+                None => Focus::LookAt
+            })
+    }
+    pub fn background(&self) -> Result<Arc<dyn Background>, SceneError> {
+        self.background.build(self)
+    }
+    pub fn build(&self) -> Result<(HittableStore, LightList, Camera), SceneError> {
+        let textures: HashMap<&str, Arc<dyn Texture>> = self.textures.iter()
+            .map(|(name, spec)| Ok((name.as_str(), spec.build(self)?)))
+            .collect::<Result<_, SceneError>>()?;
+        let materials: HashMap<&str, Arc<dyn Material>> = self.materials.iter()
+            .map(|(name, spec)| Ok((name.as_str(), spec.build(&textures)?)))
+            .collect::<Result<_, SceneError>>()?;
+        let lookup = |name: &str| materials.get(name)
+            .cloned()
+            .ok_or_else(|| SceneError::Invalid(format!("unknown material `{}`", name)));
+
+        let mut meshes: HashMap<(PathBuf, Option<&str>), Arc<Mesh>> = HashMap::new();
+        let mut volumes: HashMap<(PathBuf, Option<[usize; 3]>), Arc<GridVolume>> = HashMap::new();
+        let mut world = HittableStore::new();
+        let mut lights = LightList::new();
+        for object in self.objects.iter() {
+            let (shape, emissive): (Arc<dyn Hittable>, bool) = match &object.shape {
+                ShapeSpec::Sphere { center, radius, material } => {
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    (Arc::new(Sphere::new(*center, *radius, Some(material))), emissive)
+                }
+                ShapeSpec::MovingSphere { keyframes, radius, material } => {
+                    let keyframes = keyframes.iter().map(|keyframe| (keyframe.time, keyframe.center)).collect();
+                    (Arc::new(MovingSphere::keyframed(keyframes, *radius, Some(lookup(material)?))), false)
+                }
+                ShapeSpec::Triangle { vertices, material } => {
+                    let [p0, p1, p2] = *vertices;
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    (Arc::new(Triangle::new(p0, p1, p2, Some(material))), emissive)
+                }
+                ShapeSpec::Plane { point, normal, material } => {
+                    (Arc::new(Plane::new(*point, *normal, Some(lookup(material)?))), false)
+                }
+                ShapeSpec::Rect { min, max, flip, material } => {
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    let rect = if min.z() == max.z() {
+                        Rect::xy(min.x(), max.x(), min.y(), max.y(), min.z(), Some(material))
+                    } else if min.y() == max.y() {
+                        Rect::xz(min.x(), max.x(), min.z(), max.z(), min.y(), Some(material))
+                    } else {
+                        Rect::yz(min.y(), max.y(), min.z(), max.z(), min.x(), Some(material))
+                    };
+                    (Arc::new(rect.flipped(*flip)), emissive)
+                }
+                ShapeSpec::Quad { corner, u, v, material } => {
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    (Arc::new(Quad::new(*corner, *u, *v, Some(material))), emissive)
+                }
+                ShapeSpec::Disk { center, normal, radius, material } => {
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    (Arc::new(Disk::new(*center, *normal, *radius, Some(material))), emissive)
+                }
+                ShapeSpec::Box { min, max, material } => {
+                    let material = lookup(material)?;
+                    let emissive = material.is_emissive();
+                    (Arc::new(Cuboid::new(*min, *max, Some(material))), emissive)
+                }
+                ShapeSpec::Cylinder { base, top, radius, capped, material } => {
+                    let cylinder = Cylinder::new(*base, *top, *radius, Some(lookup(material)?)).with_caps(*capped);
+                    (Arc::new(cylinder), false)
+                }
+                ShapeSpec::Cone { base, apex, radius, capped, material } => {
+                    (Arc::new(Cone::new(*base, *apex, *radius, Some(lookup(material)?)).with_cap(*capped)), false)
+                }
+                ShapeSpec::Torus { center, normal, major_radius, minor_radius, material } => {
+                    let torus = Torus::new(*center, *normal, *major_radius, *minor_radius, Some(lookup(material)?));
+                    (Arc::new(torus), false)
+                }
+                ShapeSpec::Quadric { coefficients, min, max, material } => {
+                    let bounds = Aabb::new(*min, *max);
+                    (Arc::new(Quadric::new(*coefficients, bounds, Some(lookup(material)?))), false)
+                }
+                ShapeSpec::Mesh { path, material, displacement } => {
+                    let path = self.resolve(path);
+                    let key = (path, material.as_deref());
+                    let cached = match displacement {
+                        Some(_) => None,
+                        None => meshes.get(&key).cloned()
+                    };
+                    let mesh = match cached {
+                        Some(mesh) => mesh,
+                        None => {
+                            let material = match material {
+                                Some(name) => Some(lookup(name)?),
+                                None => None
+                            };
+                            let mesh = match displacement {
+                                Some(displacement) => {
+                                    let height = textures.get(displacement.texture.as_str())
+                                        .cloned()
+                                        .ok_or_else(|| SceneError::Invalid(format!("unknown texture `{}`", displacement.texture)))?;
+                                    let displacement = Displacement {
+                                        height,
+                                        scale: displacement.scale,
+                                        subdivisions: displacement.subdivisions,
+                                    };
+                                    Mesh::load_displaced(&key.0, material, &displacement)
+                                }
+                                None => Mesh::load(&key.0, material)
+                            };
+                            let mesh = Arc::new(mesh.map_err(|err| match err.kind() {
+                                io::ErrorKind::InvalidData => SceneError::Invalid(err.to_string()),
+                                _ => SceneError::Io(key.0.clone(), err)
+                            })?);
+                            if displacement.is_none() {
+                                meshes.insert(key, mesh.clone());
+                            }
+                            mesh
+                        }
+                    };
+                    (mesh, false)
+                }
+                ShapeSpec::Volume { path, min, max, resolution, emission, material } => {
+                    let key = (self.resolve(path), *resolution);
+                    let volume = match volumes.get(&key) {
+                        Some(volume) => volume.clone(),
+                        None => {
+                            let volume = GridVolume::load(&key.0, *resolution).map_err(|err| match err.kind() {
+                                io::ErrorKind::InvalidData => SceneError::Invalid(err.to_string()),
+                                _ => SceneError::Io(key.0.clone(), err)
+                            })?;
+                            let volume = Arc::new(volume);
+                            volumes.insert(key, volume.clone());
+                            volume
+                        }
+                    };
+                    let medium = GridMedium::new(volume, Aabb::new(*min, *max), lookup(material)?)
+                        .with_density_scale(object.density.unwrap_or(1.0))
+                        .with_emission(*emission);
+                    (Arc::new(medium), false)
+                }
+            };
+            let shape: Arc<dyn Hittable> = match object.transform() {
+                Some(transform) => Arc::new(Transformed::new(shape, transform)),
+                None => shape
+            };
+            let shape: Arc<dyn Hittable> = match object.shading(&textures)? {
+                Some(map) => Arc::new(Perturbed::new(shape, map)),
+                None => shape
+            };
+            let density = match object.shape {
+                ShapeSpec::Volume { .. } => None,
+                _ => object.density
+            };
+            let (object, emissive): (Arc<dyn Hittable>, bool) = match (density, object.shape.material()) {
+                (Some(density), Some(material)) => (Arc::new(ConstantMedium::new(shape, density, lookup(material)?)), false),
+                (Some(_), None) => return Err(SceneError::Invalid("a medium needs a material".to_string())),
+                (None, _) => (shape, emissive)
+            };
+            if emissive {
+                lights.add(object.clone());
+            }
+            world.store(Box::new(object));
+        }
+        Ok((world, lights, self.camera()))
+    }
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.base_dir {
+            Some(base_dir) if path.is_relative() => base_dir.join(path),
+            _ => path.to_path_buf()
+        }
+    }
+}
@@ -0,0 +1,1092 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::graphics::camera::has_valid_up;
+use crate::graphics::material::ConductorPreset;
+use crate::graphics::quadric::QuadricCoefficients;
+use crate::graphics::sky::SUN_DIAMETER;
+use crate::graphics::texture::{Filter, NoiseKind, WrapMode};
+use crate::scene::{
+    default_sky_bottom, default_sky_top, default_vup, BackgroundSpec, BumpMapSpec, CameraSettings, ColorSource, DisplacementSpec,
+    DispersionSpec, ImageSettings, MaterialSpec, Keyframe, NormalMapSpec, ObjectSpec, Scene, SceneError, ShapeSpec,
+    TextureSpec, TransformSpec,
+};
+
+const MAX_SUBDIVISIONS: usize = 64;
+const DEFAULT_TURBIDITY: f32 = 3.0;
+const MIN_TURBIDITY: f32 = 1.7;
+const MAX_TURBIDITY: f32 = 10.0;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawScene {
+    image: Option<RawImage>,
+    camera: RawCamera,
+    background: Option<Spanned<RawBackground>>,
+    #[serde(default)]
+    textures: BTreeMap<String, Spanned<RawTexture>>,
+    #[serde(default)]
+    materials: BTreeMap<String, Spanned<RawMaterial>>,
+    #[serde(default)]
+    objects: Vec<Spanned<RawObject>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawImage {
+    aspect_ratio: Option<Spanned<f32>>,
+    width: Option<Spanned<i32>>,
+    samples_per_pixel: Option<Spanned<i32>>,
+    max_depth: Option<Spanned<i32>>,
+    spectral: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCamera {
+    look_from: Point3,
+    look_at: Spanned<Point3>,
+    vup: Option<Spanned<Vec3>>,
+    vfov: Spanned<f32>,
+    aperture: Option<Spanned<f32>>,
+    focus_distance: Option<Spanned<f32>>,
+    shutter_open: Option<Spanned<f32>>,
+    shutter_close: Option<Spanned<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBackground {
+    #[serde(rename = "type")]
+    kind: Spanned<String>,
+    bottom: Option<Color>,
+    top: Option<Color>,
+    color: Option<Color>,
+    path: Option<String>,
+    faces: Option<[String; 6]>,
+    rotation: Option<f32>,
+    intensity: Option<Spanned<f32>>,
+    sun_direction: Option<Spanned<Vec3>>,
+    turbidity: Option<Spanned<f32>>,
+    ground_albedo: Option<Spanned<Color>>,
+    sun_diameter: Option<Spanned<f32>>,
+}
+
+impl RawBackground {
+    fn fields(&self) -> [(&'static str, bool); 11] {
+        [
+            ("bottom", self.bottom.is_some()),
+            ("top", self.top.is_some()),
+            ("color", self.color.is_some()),
+            ("path", self.path.is_some()),
+            ("faces", self.faces.is_some()),
+            ("rotation", self.rotation.is_some()),
+            ("intensity", self.intensity.is_some()),
+            ("sun_direction", self.sun_direction.is_some()),
+            ("turbidity", self.turbidity.is_some()),
+            ("ground_albedo", self.ground_albedo.is_some()),
+            ("sun_diameter", self.sun_diameter.is_some()),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTexture {
+    #[serde(rename = "type")]
+    kind: Spanned<String>,
+    color: Option<Color>,
+    odd: Option<Color>,
+    even: Option<Color>,
+    scale: Option<Spanned<f32>>,
+    path: Option<String>,
+    wrap: Option<WrapMode>,
+    filter: Option<Filter>,
+    noise: Option<NoiseKind>,
+    octaves: Option<Spanned<u32>>,
+    seed: Option<u64>,
+    low: Option<Color>,
+    high: Option<Color>,
+    linear: Option<bool>,
+}
+
+impl RawTexture {
+    fn fields(&self) -> [(&'static str, bool); 13] {
+        [
+            ("color", self.color.is_some()),
+            ("odd", self.odd.is_some()),
+            ("even", self.even.is_some()),
+            ("scale", self.scale.is_some()),
+            ("path", self.path.is_some()),
+            ("wrap", self.wrap.is_some()),
+            ("filter", self.filter.is_some()),
+            ("noise", self.noise.is_some()),
+            ("octaves", self.octaves.is_some()),
+            ("seed", self.seed.is_some()),
+            ("low", self.low.is_some()),
+            ("high", self.high.is_some()),
+            ("linear", self.linear.is_some()),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "expected a color, a number or the name of a texture")]
+enum RawColorSource {
+    Color(Color),
+    Value(f32),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMaterial {
+    #[serde(rename = "type")]
+    kind: Spanned<String>,
+    albedo: Option<Spanned<RawColorSource>>,
+    fuzz: Option<f32>,
+    ref_idx: Option<Spanned<f32>>,
+    dispersion: Option<Spanned<DispersionSpec>>,
+    emit: Option<Spanned<RawColorSource>>,
+    two_sided: Option<bool>,
+    anisotropy: Option<Spanned<f32>>,
+    roughness: Option<Spanned<RawColorSource>>,
+    preset: Option<ConductorPreset>,
+    eta: Option<Spanned<Color>>,
+    k: Option<Spanned<Color>>,
+    base_color: Option<Spanned<RawColorSource>>,
+    metallic: Option<Spanned<RawColorSource>>,
+    specular: Option<Spanned<RawColorSource>>,
+    sheen: Option<Spanned<RawColorSource>>,
+    clearcoat: Option<Spanned<RawColorSource>>,
+    clearcoat_roughness: Option<Spanned<RawColorSource>>,
+    transmission: Option<Spanned<RawColorSource>>,
+    ior: Option<Spanned<f32>>,
+}
+
+impl RawMaterial {
+    fn fields(&self) -> [(&'static str, bool); 19] {
+        [
+            ("albedo", self.albedo.is_some()),
+            ("fuzz", self.fuzz.is_some()),
+            ("ref_idx", self.ref_idx.is_some()),
+            ("dispersion", self.dispersion.is_some()),
+            ("emit", self.emit.is_some()),
+            ("two_sided", self.two_sided.is_some()),
+            ("anisotropy", self.anisotropy.is_some()),
+            ("roughness", self.roughness.is_some()),
+            ("preset", self.preset.is_some()),
+            ("eta", self.eta.is_some()),
+            ("k", self.k.is_some()),
+            ("base_color", self.base_color.is_some()),
+            ("metallic", self.metallic.is_some()),
+            ("specular", self.specular.is_some()),
+            ("sheen", self.sheen.is_some()),
+            ("clearcoat", self.clearcoat.is_some()),
+            ("clearcoat_roughness", self.clearcoat_roughness.is_some()),
+            ("transmission", self.transmission.is_some()),
+            ("ior", self.ior.is_some()),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawNormalMap {
+    texture: Spanned<String>,
+    strength: Option<Spanned<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBumpMap {
+    texture: Spanned<String>,
+    scale: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDisplacement {
+    texture: Spanned<String>,
+    scale: Option<f32>,
+    subdivisions: Option<Spanned<usize>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawObject {
+    #[serde(rename = "type")]
+    kind: Spanned<String>,
+    center: Option<Point3>,
+    radius: Option<Spanned<f32>>,
+    vertices: Option<[Point3; 3]>,
+    keyframes: Option<Spanned<Vec<Keyframe>>>,
+    point: Option<Point3>,
+    normal: Option<Spanned<Vec3>>,
+    min: Option<Point3>,
+    max: Option<Spanned<Point3>>,
+    flip: Option<bool>,
+    corner: Option<Point3>,
+    u: Option<Vec3>,
+    v: Option<Spanned<Vec3>>,
+    base: Option<Point3>,
+    top: Option<Spanned<Point3>>,
+    apex: Option<Spanned<Point3>>,
+    capped: Option<bool>,
+    major_radius: Option<Spanned<f32>>,
+    minor_radius: Option<Spanned<f32>>,
+    coefficients: Option<QuadricCoefficients>,
+    path: Option<String>,
+    resolution: Option<Spanned<[usize; 3]>>,
+    emission: Option<Spanned<f32>>,
+    material: Option<Spanned<String>>,
+    density: Option<Spanned<f32>>,
+    normal_map: Option<Spanned<RawNormalMap>>,
+    bump_map: Option<Spanned<RawBumpMap>>,
+    displacement: Option<Spanned<RawDisplacement>>,
+    #[serde(default)]
+    transform: Vec<Spanned<TransformSpec>>,
+}
+
+impl RawObject {
+    fn fields(&self) -> [(&'static str, bool); 22] {
+        [
+            ("center", self.center.is_some()),
+            ("radius", self.radius.is_some()),
+            ("vertices", self.vertices.is_some()),
+            ("keyframes", self.keyframes.is_some()),
+            ("point", self.point.is_some()),
+            ("normal", self.normal.is_some()),
+            ("min", self.min.is_some()),
+            ("max", self.max.is_some()),
+            ("flip", self.flip.is_some()),
+            ("corner", self.corner.is_some()),
+            ("u", self.u.is_some()),
+            ("v", self.v.is_some()),
+            ("base", self.base.is_some()),
+            ("top", self.top.is_some()),
+            ("apex", self.apex.is_some()),
+            ("capped", self.capped.is_some()),
+            ("major_radius", self.major_radius.is_some()),
+            ("minor_radius", self.minor_radius.is_some()),
+            ("coefficients", self.coefficients.is_some()),
+            ("path", self.path.is_some()),
+            ("resolution", self.resolution.is_some()),
+            ("emission", self.emission.is_some()),
+        ]
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+}
+
+fn inner<T>(value: Option<Spanned<T>>) -> Option<T> {
+    value.map(Spanned::into_inner)
+}
+
+pub fn parse(source: &str) -> Result<Scene, SceneError> {
+    let parser = Parser { source };
+    let raw: RawScene = toml::from_str(source).map_err(|err| {
+        let offset = err.span().map_or(0, |span| span.start);
+        parser.error(offset..offset, err.message().to_string())
+    })?;
+    parser.scene(raw)
+}
+
+impl<'a> Parser<'a> {
+    fn location(&self, offset: usize) -> (usize, usize) {
+        let prefix = &self.source[..offset.min(self.source.len())];
+        let line = prefix.matches('\n').count() + 1;
+        let column = prefix.rsplit('\n').next().map_or(0, |text| text.chars().count()) + 1;
+        (line, column)
+    }
+
+    fn error(&self, span: Range<usize>, message: String) -> SceneError {
+        let (line, column) = self.location(span.start);
+        SceneError::Parse {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn required<T>(&self, value: Option<T>, span: Range<usize>, kind: &str, field: &str) -> Result<T, SceneError> {
+        value.ok_or_else(|| self.error(span, format!("{} requires `{}`", kind, field)))
+    }
+
+    fn reject(&self, span: Range<usize>, kind: &str, fields: &[(&str, bool)]) -> Result<(), SceneError> {
+        match fields.iter().find(|(_, present)| *present) {
+            Some((field, _)) => Err(self.error(span, format!("`{}` is not a valid field for {}", field, kind))),
+            None => Ok(())
+        }
+    }
+
+    fn only(&self, span: Range<usize>, kind: &str, fields: &[(&str, bool)], allowed: &[&str]) -> Result<(), SceneError> {
+        let rejected: Vec<(&str, bool)> = fields.iter()
+            .map(|&(field, present)| (field, present && !allowed.contains(&field)))
+            .collect();
+        self.reject(span, kind, &rejected)
+    }
+
+    fn check<T>(&self, value: Option<&Spanned<T>>, valid: impl Fn(&T) -> bool, message: &str) -> Result<(), SceneError> {
+        match value {
+            Some(value) if !valid(value.get_ref()) => Err(self.error(value.span(), message.to_string())),
+            _ => Ok(())
+        }
+    }
+
+    fn positive(&self, value: Spanned<f32>, field: &str) -> Result<f32, SceneError> {
+        self.check(Some(&value), |&value| value > 0.0, &format!("`{}` must be positive", field))?;
+        Ok(value.into_inner())
+    }
+
+    fn scene(&self, raw: RawScene) -> Result<Scene, SceneError> {
+        let image = match raw.image {
+            Some(image) => self.image(image)?,
+            None => ImageSettings::default()
+        };
+        let camera = self.camera(raw.camera)?;
+
+        let mut scene = Scene::new(image, camera);
+        if let Some(background) = raw.background {
+            scene.background = self.background(background)?;
+        }
+        for (name, texture) in raw.textures.into_iter() {
+            let texture = self.texture(texture)?;
+            scene.textures.insert(name, texture);
+        }
+        for (name, material) in raw.materials.into_iter() {
+            let material = self.material(material, &scene.textures)?;
+            scene.materials.insert(name, material);
+        }
+        for object in raw.objects.into_iter() {
+            let object = self.object(object, &scene.materials, &scene.textures)?;
+            scene.objects.push(object);
+        }
+        Ok(scene)
+    }
+
+    fn image(&self, raw: RawImage) -> Result<ImageSettings, SceneError> {
+        self.check(raw.aspect_ratio.as_ref(), |&aspect_ratio| aspect_ratio > 0.0, "`aspect_ratio` must be positive")?;
+        self.check(raw.width.as_ref(), |&width| width > 1, "`width` must be at least 2")?;
+        self.check(raw.samples_per_pixel.as_ref(), |&samples| samples > 0, "`samples_per_pixel` must be positive")?;
+        self.check(raw.max_depth.as_ref(), |&depth| depth > 0, "`max_depth` must be positive")?;
+        let span = raw.width.as_ref().map(Spanned::span)
+            .or_else(|| raw.aspect_ratio.as_ref().map(Spanned::span))
+            .unwrap_or(0..0);
+        let defaults = ImageSettings::default();
+        let image = ImageSettings {
+            aspect_ratio: inner(raw.aspect_ratio).unwrap_or(defaults.aspect_ratio),
+            width: inner(raw.width).unwrap_or(defaults.width),
+            samples_per_pixel: inner(raw.samples_per_pixel).unwrap_or(defaults.samples_per_pixel),
+            max_depth: inner(raw.max_depth).unwrap_or(defaults.max_depth),
+            spectral: raw.spectral.unwrap_or(defaults.spectral),
+        };
+        if ((image.width as f32 / image.aspect_ratio) as i32) < 2 {
+            return Err(self.error(span, "`width` / `aspect_ratio` must give an image at least 2 pixels tall".to_string()));
+        }
+        Ok(image)
+    }
+
+    fn camera(&self, raw: RawCamera) -> Result<CameraSettings, SceneError> {
+        self.check(Some(&raw.vfov), |&vfov| vfov > 0.0 && vfov < 180.0, "`vfov` must be between 0 and 180 degrees")?;
+        self.check(Some(&raw.look_at), |&look_at| (raw.look_from - look_at).length_square() > 0.0, "`look_from` and `look_at` must differ")?;
+        let view = raw.look_from - *raw.look_at.get_ref();
+        let vup = raw.vup.as_ref().map_or(default_vup(), |vup| *vup.get_ref());
+        if !has_valid_up(view, vup) {
+            let span = raw.vup.as_ref().map_or(raw.look_at.span(), Spanned::span);
+            return Err(self.error(span, "`vup` must not be parallel to the view direction".to_string()));
+        }
+        self.check(raw.aperture.as_ref(), |&aperture| aperture >= 0.0, "`aperture` must not be negative")?;
+        self.check(raw.focus_distance.as_ref(), |&distance| distance > 0.0, "`focus_distance` must be positive")?;
+        let shutter_open = raw.shutter_open.as_ref().map_or(0.0, |open| *open.get_ref());
+        let shutter_span = raw.shutter_close.as_ref().or(raw.shutter_open.as_ref()).map(Spanned::span);
+        let shutter_close = inner(raw.shutter_close).unwrap_or(0.0);
+        if shutter_close < shutter_open {
+            return Err(self.error(shutter_span.unwrap_or(0..0), "`shutter_close` must not be before `shutter_open`".to_string()));
+        }
+        Ok(CameraSettings {
+            look_from: raw.look_from,
+            look_at: raw.look_at.into_inner(),
+            vup: inner(raw.vup).unwrap_or_else(default_vup),
+            vfov: raw.vfov.into_inner(),
+            aperture: inner(raw.aperture).unwrap_or(0.0),
+            focus_distance: inner(raw.focus_distance),
+            shutter_open,
+            shutter_close,
+        })
+    }
+
+    fn background(&self, raw: Spanned<RawBackground>) -> Result<BackgroundSpec, SceneError> {
+        let span = raw.span();
+        let raw = raw.into_inner();
+        let kind = raw.kind.get_ref().as_str();
+        let fields = raw.fields();
+        match kind {
+            "gradient" => {
+                self.only(span, kind, &fields, &["bottom", "top"])?;
+                Ok(BackgroundSpec::Gradient {
+                    bottom: raw.bottom.unwrap_or_else(default_sky_bottom),
+                    top: raw.top.unwrap_or_else(default_sky_top),
+                })
+            }
+            "solid" => {
+                self.only(span.clone(), kind, &fields, &["color"])?;
+                Ok(BackgroundSpec::Solid {
+                    color: self.required(raw.color, span, kind, "color")?,
+                })
+            }
+            "none" => {
+                self.only(span, kind, &fields, &[])?;
+                Ok(BackgroundSpec::Solid {
+                    color: Color::new(0.0, 0.0, 0.0),
+                })
+            }
+            "environment" => {
+                self.only(span.clone(), kind, &fields, &["path", "rotation", "intensity"])?;
+                let intensity = self.environment_intensity(raw.intensity)?;
+                Ok(BackgroundSpec::Environment {
+                    path: PathBuf::from(self.required(raw.path, span, kind, "path")?),
+                    rotation: raw.rotation.unwrap_or(0.0),
+                    intensity,
+                })
+            }
+            "cube_map" => {
+                self.only(span.clone(), kind, &fields, &["faces", "rotation", "intensity"])?;
+                let intensity = self.environment_intensity(raw.intensity)?;
+                Ok(BackgroundSpec::CubeMap {
+                    faces: self.required(raw.faces, span, kind, "faces")?.map(PathBuf::from),
+                    rotation: raw.rotation.unwrap_or(0.0),
+                    intensity,
+                })
+            }
+            "sky" => {
+                self.only(span.clone(), kind, &fields, &["sun_direction", "turbidity", "ground_albedo", "sun_diameter", "intensity"])?;
+                let sun_direction = self.required(raw.sun_direction, span, kind, "sun_direction")?;
+                self.check(Some(&sun_direction), |direction| direction.y() > 0.0, "`sun_direction` must point above the horizon")?;
+                self.check(
+                    raw.turbidity.as_ref(),
+                    |turbidity| (MIN_TURBIDITY..=MAX_TURBIDITY).contains(turbidity),
+                    &format!("`turbidity` must be between {} and {}", MIN_TURBIDITY, MAX_TURBIDITY),
+                )?;
+                self.check(
+                    raw.ground_albedo.as_ref(),
+                    |albedo| (0..3).all(|channel| (0.0..=1.0).contains(&albedo[channel])),
+                    "`ground_albedo` must be between 0 and 1",
+                )?;
+                let sun_diameter = match raw.sun_diameter {
+                    Some(sun_diameter) => self.positive(sun_diameter, "sun_diameter")?,
+                    None => SUN_DIAMETER
+                };
+                let intensity = self.environment_intensity(raw.intensity)?;
+                let sun_direction = sun_direction.into_inner();
+                let turbidity = inner(raw.turbidity).unwrap_or(DEFAULT_TURBIDITY);
+                let ground_albedo = inner(raw.ground_albedo).unwrap_or_else(|| Color::new(0.3, 0.3, 0.3));
+                Ok(BackgroundSpec::Sky { sun_direction, turbidity, ground_albedo, sun_diameter, intensity })
+            }
+            _ => Err(self.error(raw.kind.span(), format!("unknown background type `{}`", kind)))
+        }
+    }
+
+    fn environment_intensity(&self, intensity: Option<Spanned<f32>>) -> Result<f32, SceneError> {
+        self.check(intensity.as_ref(), |&intensity| intensity >= 0.0, "`intensity` must not be negative")?;
+        Ok(inner(intensity).unwrap_or(1.0))
+    }
+
+    fn texture(&self, raw: Spanned<RawTexture>) -> Result<TextureSpec, SceneError> {
+        let span = raw.span();
+        let raw = raw.into_inner();
+        let kind = raw.kind.get_ref().as_str();
+        let fields = raw.fields();
+        match kind {
+            "solid" => {
+                self.only(span.clone(), kind, &fields, &["color"])?;
+                Ok(TextureSpec::Solid {
+                    color: self.required(raw.color, span, kind, "color")?,
+                })
+            }
+            "checker" | "uv_checker" => {
+                self.only(span.clone(), kind, &fields, &["odd", "even", "scale"])?;
+                let odd = self.required(raw.odd, span.clone(), kind, "odd")?;
+                let even = self.required(raw.even, span.clone(), kind, "even")?;
+                let scale = self.required(raw.scale, span, kind, "scale")?;
+                let scale = self.positive(scale, "scale")?;
+                if kind == "checker" {
+                    return Ok(TextureSpec::Checker { odd, even, scale });
+                }
+                Ok(TextureSpec::UvChecker { odd, even, scale })
+            }
+            "image" => {
+                self.only(span.clone(), kind, &fields, &["path", "wrap", "filter", "linear"])?;
+                Ok(TextureSpec::Image {
+                    path: PathBuf::from(self.required(raw.path, span, kind, "path")?),
+                    wrap: raw.wrap.unwrap_or_default(),
+                    filter: raw.filter.unwrap_or_default(),
+                    linear: raw.linear.unwrap_or(false),
+                })
+            }
+            "noise" | "marble" => {
+                let allowed: &[&str] = match kind {
+                    "marble" => &["scale", "octaves", "seed", "low", "high"],
+                    _ => &["noise", "scale", "octaves", "seed", "low", "high"],
+                };
+                self.only(span.clone(), kind, &fields, allowed)?;
+                let scale = match raw.scale {
+                    Some(scale) => self.positive(scale, "scale")?,
+                    None => 1.0
+                };
+                self.check(raw.octaves.as_ref(), |&octaves| octaves > 0, "`octaves` must be positive")?;
+                let octaves = inner(raw.octaves).unwrap_or(7);
+                let seed = raw.seed.unwrap_or(0);
+                let low = raw.low.unwrap_or_else(|| Color::new(0.0, 0.0, 0.0));
+                let high = raw.high.unwrap_or_else(|| Color::new(1.0, 1.0, 1.0));
+                if kind == "marble" {
+                    return Ok(TextureSpec::Marble { scale, octaves, seed, low, high });
+                }
+                Ok(TextureSpec::Noise {
+                    noise: raw.noise.unwrap_or_default(),
+                    scale,
+                    octaves,
+                    seed,
+                    low,
+                    high,
+                })
+            }
+            _ => Err(self.error(raw.kind.span(), format!("unknown texture type `{}`", kind)))
+        }
+    }
+
+    fn color_source(&self, raw: Spanned<RawColorSource>, textures: &BTreeMap<String, TextureSpec>) -> Result<ColorSource, SceneError> {
+        let span = raw.span();
+        match raw.into_inner() {
+            RawColorSource::Color(color) => Ok(ColorSource::Color(color)),
+            RawColorSource::Value(value) => Ok(ColorSource::Value(value)),
+            RawColorSource::Texture(name) => {
+                if !textures.contains_key(&name) {
+                    return Err(self.error(span, format!("unknown texture `{}`", name)));
+                }
+                Ok(ColorSource::Texture(name))
+            }
+        }
+    }
+
+    fn optional_color_source(&self, raw: Option<Spanned<RawColorSource>>, textures: &BTreeMap<String, TextureSpec>) -> Result<Option<ColorSource>, SceneError> {
+        match raw {
+            Some(raw) => Ok(Some(self.color_source(raw, textures)?)),
+            None => Ok(None)
+        }
+    }
+
+    fn material(&self, raw: Spanned<RawMaterial>, textures: &BTreeMap<String, TextureSpec>) -> Result<MaterialSpec, SceneError> {
+        let span = raw.span();
+        let raw = raw.into_inner();
+        let kind = raw.kind.get_ref().as_str();
+        let fields = raw.fields();
+        match kind {
+            "lambertian" => {
+                self.only(span.clone(), kind, &fields, &["albedo"])?;
+                let albedo = self.optional_color_source(raw.albedo, textures)?;
+                Ok(MaterialSpec::Lambertian {
+                    albedo: self.required(albedo, span, kind, "albedo")?,
+                })
+            }
+            "metal" => {
+                self.only(span.clone(), kind, &fields, &["albedo", "fuzz"])?;
+                let albedo = self.optional_color_source(raw.albedo, textures)?;
+                Ok(MaterialSpec::Metal {
+                    albedo: self.required(albedo, span, kind, "albedo")?,
+                    fuzz: raw.fuzz.unwrap_or(0.0),
+                })
+            }
+            "dielectric" => {
+                self.only(span.clone(), kind, &fields, &["ref_idx", "dispersion", "roughness", "anisotropy"])?;
+                match (&raw.ref_idx, &raw.dispersion) {
+                    (Some(_), Some(dispersion)) => {
+                        return Err(self.error(dispersion.span(), "a dielectric takes either `ref_idx` or `dispersion`".to_string()));
+                    }
+                    (None, None) => {
+                        return Err(self.error(span, "dielectric requires `ref_idx` or `dispersion`".to_string()));
+                    }
+                    _ => {}
+                }
+                self.check(raw.ref_idx.as_ref(), |&ref_idx| ref_idx > 0.0, "`ref_idx` must be positive")?;
+                if let Some(dispersion) = &raw.dispersion {
+                    match dispersion.get_ref() {
+                        DispersionSpec::Cauchy { a, b } if *a <= 0.0 || *b < 0.0 => {
+                            return Err(self.error(dispersion.span(), "cauchy dispersion requires a positive `a` and a non-negative `b`".to_string()));
+                        }
+                        DispersionSpec::Sellmeier { b, c } if (0..3).any(|i| b[i] < 0.0 || c[i] < 0.0) => {
+                            return Err(self.error(dispersion.span(), "sellmeier coefficients must not be negative".to_string()));
+                        }
+                        _ => {}
+                    }
+                }
+                let (roughness, anisotropy) = self.microfacet(&raw)?;
+                Ok(MaterialSpec::Dielectric {
+                    ref_idx: inner(raw.ref_idx),
+                    dispersion: inner(raw.dispersion),
+                    roughness,
+                    anisotropy,
+                })
+            }
+            "conductor" => {
+                self.only(span.clone(), kind, &fields, &["preset", "eta", "k", "roughness", "anisotropy"])?;
+                let (eta, k) = match (raw.preset, &raw.eta, &raw.k) {
+                    (Some(preset), None, None) => (preset.eta(), preset.k()),
+                    (Some(_), eta, k) => {
+                        let conflict = eta.as_ref().or(k.as_ref()).map_or(span, Spanned::span);
+                        return Err(self.error(conflict, "a conductor takes either a `preset` or `eta` and `k`".to_string()));
+                    }
+                    (None, eta, k) => {
+                        let eta = self.required(eta.as_ref(), span.clone(), kind, "eta")?;
+                        let k = self.required(k.as_ref(), span, kind, "k")?;
+                        self.check(Some(eta), |eta| (0..3).all(|channel| eta[channel] > 0.0), "`eta` must be positive")?;
+                        self.check(Some(k), |k| (0..3).all(|channel| k[channel] >= 0.0), "`k` must not be negative")?;
+                        (*eta.get_ref(), *k.get_ref())
+                    }
+                };
+                let (roughness, anisotropy) = self.microfacet(&raw)?;
+                Ok(MaterialSpec::Conductor { eta, k, roughness, anisotropy })
+            }
+            "diffuse_light" => {
+                self.only(span.clone(), kind, &fields, &["emit", "two_sided"])?;
+                let emit = self.optional_color_source(raw.emit, textures)?;
+                Ok(MaterialSpec::DiffuseLight {
+                    emit: self.required(emit, span, kind, "emit")?,
+                    two_sided: raw.two_sided.unwrap_or(false),
+                })
+            }
+            "isotropic" => {
+                self.only(span.clone(), kind, &fields, &["albedo"])?;
+                let albedo = self.optional_color_source(raw.albedo, textures)?;
+                Ok(MaterialSpec::Isotropic {
+                    albedo: self.required(albedo, span, kind, "albedo")?,
+                })
+            }
+            "henyey_greenstein" => {
+                self.only(span.clone(), kind, &fields, &["albedo", "anisotropy"])?;
+                let albedo = self.optional_color_source(raw.albedo, textures)?;
+                let albedo = self.required(albedo, span, kind, "albedo")?;
+                self.check(raw.anisotropy.as_ref(), |&anisotropy| anisotropy > -1.0 && anisotropy < 1.0, "`anisotropy` must be between -1 and 1")?;
+                let anisotropy = inner(raw.anisotropy).unwrap_or(0.0);
+                Ok(MaterialSpec::HenyeyGreenstein { albedo, anisotropy })
+            }
+            "principled" => {
+                self.only(span.clone(), kind, &fields, &[
+                    "base_color", "metallic", "roughness", "specular", "sheen", "clearcoat", "clearcoat_roughness",
+                    "transmission", "emit", "ior", "anisotropy",
+                ])?;
+                let base_color = self.optional_color_source(raw.base_color, textures)?;
+                let ior = match raw.ior {
+                    Some(ior) => self.positive(ior, "ior")?,
+                    None => 1.5
+                };
+                self.check(raw.anisotropy.as_ref(), |anisotropy| (-1.0..=1.0).contains(anisotropy), "`anisotropy` must be between -1 and 1")?;
+                let anisotropy = inner(raw.anisotropy).unwrap_or(0.0);
+                Ok(MaterialSpec::Principled {
+                    base_color: self.required(base_color, span, kind, "base_color")?,
+                    metallic: self.weight(raw.metallic, textures, "metallic")?,
+                    roughness: self.weight(raw.roughness, textures, "roughness")?,
+                    specular: self.weight(raw.specular, textures, "specular")?,
+                    sheen: self.weight(raw.sheen, textures, "sheen")?,
+                    clearcoat: self.weight(raw.clearcoat, textures, "clearcoat")?,
+                    clearcoat_roughness: self.weight(raw.clearcoat_roughness, textures, "clearcoat_roughness")?,
+                    transmission: self.weight(raw.transmission, textures, "transmission")?,
+                    emit: self.optional_color_source(raw.emit, textures)?,
+                    ior,
+                    anisotropy,
+                })
+            }
+            _ => Err(self.error(raw.kind.span(), format!("unknown material type `{}`", kind)))
+        }
+    }
+
+    fn weight(&self, raw: Option<Spanned<RawColorSource>>, textures: &BTreeMap<String, TextureSpec>, field: &str) -> Result<Option<ColorSource>, SceneError> {
+        if let Some(raw) = &raw {
+            if let RawColorSource::Value(value) = raw.get_ref() {
+                if !(0.0..=1.0).contains(value) {
+                    return Err(self.error(raw.span(), format!("`{}` must be between 0 and 1", field)));
+                }
+            }
+        }
+        self.optional_color_source(raw, textures)
+    }
+
+    fn microfacet(&self, raw: &RawMaterial) -> Result<(f32, f32), SceneError> {
+        let roughness = match &raw.roughness {
+            None => 0.0,
+            Some(roughness) => match roughness.get_ref() {
+                RawColorSource::Value(value) if (0.0..=1.0).contains(value) => *value,
+                _ => return Err(self.error(roughness.span(), "`roughness` must be between 0 and 1".to_string()))
+            }
+        };
+        self.check(raw.anisotropy.as_ref(), |anisotropy| (-1.0..=1.0).contains(anisotropy), "`anisotropy` must be between -1 and 1")?;
+        Ok((roughness, raw.anisotropy.as_ref().map_or(0.0, |anisotropy| *anisotropy.get_ref())))
+    }
+
+    fn material_name(&self, name: Spanned<String>, materials: &BTreeMap<String, MaterialSpec>) -> Result<String, SceneError> {
+        if !materials.contains_key(name.get_ref()) {
+            return Err(self.error(name.span(), format!("unknown material `{}`", name.get_ref())));
+        }
+        Ok(name.into_inner())
+    }
+
+    fn direction(&self, value: Option<Spanned<Vec3>>, span: Range<usize>, kind: &str, field: &str) -> Result<Vec3, SceneError> {
+        let direction = self.required(value, span, kind, field)?;
+        self.check(Some(&direction), |direction| direction.length_square() > 0.0, &format!("`{}` must not be zero", field))?;
+        Ok(direction.into_inner())
+    }
+
+    fn radius(&self, value: Option<Spanned<f32>>, span: Range<usize>, kind: &str) -> Result<f32, SceneError> {
+        let radius = self.required(value, span, kind, "radius")?;
+        self.check(Some(&radius), |&radius| radius != 0.0, "`radius` must not be zero")?;
+        Ok(radius.into_inner())
+    }
+
+    fn transform(&self, raw: Spanned<TransformSpec>) -> Result<TransformSpec, SceneError> {
+        let span = raw.span();
+        let transform = raw.into_inner();
+        match &transform {
+            TransformSpec::Scale(factors) if factors.x() == 0.0 || factors.y() == 0.0 || factors.z() == 0.0 => {
+                Err(self.error(span, "`scale` factors must not be zero".to_string()))
+            }
+            TransformSpec::Rotate { axis, .. } if axis.length_square() == 0.0 => {
+                Err(self.error(span, "`axis` must not be zero".to_string()))
+            }
+            _ => Ok(transform)
+        }
+    }
+
+    fn object(&self, raw: Spanned<RawObject>, materials: &BTreeMap<String, MaterialSpec>, textures: &BTreeMap<String, TextureSpec>) -> Result<ObjectSpec, SceneError> {
+        let span = raw.span();
+        let mut raw = raw.into_inner();
+        if let (Some(_), Some(bump_map)) = (&raw.normal_map, &raw.bump_map) {
+            return Err(self.error(bump_map.span(), "an object takes either a `normal_map` or a `bump_map`".to_string()));
+        }
+        let normal_map = match raw.normal_map.take() {
+            Some(map) => Some(self.normal_map(map, textures)?),
+            None => None
+        };
+        let bump_map = match raw.bump_map.take() {
+            Some(map) => Some(self.bump_map(map, textures)?),
+            None => None
+        };
+        let displacement_span = raw.displacement.as_ref().map(Spanned::span);
+        let displacement = match raw.displacement.take() {
+            Some(displacement) => Some(self.displacement(displacement, textures)?),
+            None => None
+        };
+        let transform = std::mem::take(&mut raw.transform).into_iter()
+            .map(|transform| self.transform(transform))
+            .collect::<Result<Vec<TransformSpec>, SceneError>>()?;
+        let density = match raw.density.take() {
+            Some(density) if raw.material.is_none() => {
+                return Err(self.error(density.span(), format!("a {} with a `density` requires `material`", raw.kind.get_ref())));
+            }
+            Some(density) => Some(self.positive(density, "density")?),
+            None => None
+        };
+        let mut shape = self.shape(span, raw, materials)?;
+        if let Some(displacement) = displacement {
+            match &mut shape {
+                ShapeSpec::Mesh { displacement: slot, .. } => *slot = Some(displacement),
+                _ => return Err(self.error(displacement_span.unwrap_or(0..0), "only a mesh takes a `displacement`".to_string()))
+            }
+        }
+        Ok(ObjectSpec { shape, density, normal_map, bump_map, transform })
+    }
+
+    fn texture_name(&self, name: Spanned<String>, textures: &BTreeMap<String, TextureSpec>) -> Result<String, SceneError> {
+        if !textures.contains_key(name.get_ref()) {
+            return Err(self.error(name.span(), format!("unknown texture `{}`", name.get_ref())));
+        }
+        Ok(name.into_inner())
+    }
+
+    fn normal_map(&self, raw: Spanned<RawNormalMap>, textures: &BTreeMap<String, TextureSpec>) -> Result<NormalMapSpec, SceneError> {
+        let raw = raw.into_inner();
+        self.check(raw.strength.as_ref(), |&strength| strength >= 0.0, "`strength` must not be negative")?;
+        Ok(NormalMapSpec {
+            texture: self.texture_name(raw.texture, textures)?,
+            strength: inner(raw.strength).unwrap_or(1.0),
+        })
+    }
+
+    fn bump_map(&self, raw: Spanned<RawBumpMap>, textures: &BTreeMap<String, TextureSpec>) -> Result<BumpMapSpec, SceneError> {
+        let raw = raw.into_inner();
+        Ok(BumpMapSpec {
+            texture: self.texture_name(raw.texture, textures)?,
+            scale: raw.scale.unwrap_or(1.0),
+        })
+    }
+
+    fn displacement(&self, raw: Spanned<RawDisplacement>, textures: &BTreeMap<String, TextureSpec>) -> Result<DisplacementSpec, SceneError> {
+        let raw = raw.into_inner();
+        self.check(
+            raw.subdivisions.as_ref(),
+            |subdivisions| (1..=MAX_SUBDIVISIONS).contains(subdivisions),
+            &format!("`subdivisions` must be between 1 and {}", MAX_SUBDIVISIONS),
+        )?;
+        Ok(DisplacementSpec {
+            texture: self.texture_name(raw.texture, textures)?,
+            scale: raw.scale.unwrap_or(1.0),
+            subdivisions: inner(raw.subdivisions).unwrap_or(4),
+        })
+    }
+
+    fn shape(&self, span: Range<usize>, raw: RawObject, materials: &BTreeMap<String, MaterialSpec>) -> Result<ShapeSpec, SceneError> {
+        let kind = raw.kind.get_ref().as_str();
+        let fields = raw.fields();
+        match kind {
+            "sphere" => {
+                self.only(span.clone(), kind, &fields, &["center", "radius"])?;
+                let center = self.required(raw.center, span.clone(), kind, "center")?;
+                let radius = self.radius(raw.radius, span.clone(), kind)?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Sphere {
+                    center,
+                    radius,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "moving_sphere" => {
+                self.only(span.clone(), kind, &fields, &["keyframes", "radius"])?;
+                let keyframes = self.required(raw.keyframes, span.clone(), kind, "keyframes")?;
+                self.check(Some(&keyframes), |keyframes| !keyframes.is_empty(), "`keyframes` must not be empty")?;
+                let keyframes = keyframes.into_inner();
+                let radius = self.radius(raw.radius, span.clone(), kind)?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::MovingSphere {
+                    keyframes,
+                    radius,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "triangle" => {
+                self.only(span.clone(), kind, &fields, &["vertices"])?;
+                let vertices = self.required(raw.vertices, span.clone(), kind, "vertices")?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Triangle {
+                    vertices,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "plane" => {
+                self.only(span.clone(), kind, &fields, &["point", "normal"])?;
+                let point = self.required(raw.point, span.clone(), kind, "point")?;
+                let normal = self.direction(raw.normal, span.clone(), kind, "normal")?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Plane {
+                    point,
+                    normal,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "rect" => {
+                self.only(span.clone(), kind, &fields, &["min", "max", "flip"])?;
+                let min = self.required(raw.min, span.clone(), kind, "min")?;
+                let max = self.required(raw.max, span.clone(), kind, "max")?;
+                self.check(
+                    Some(&max),
+                    |max| (0..3).filter(|&axis| min[axis] == max[axis]).count() == 1,
+                    "`min` and `max` of a rect must share exactly one coordinate",
+                )?;
+                let max = max.into_inner();
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Rect {
+                    min,
+                    max,
+                    flip: raw.flip.unwrap_or(false),
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "quad" => {
+                self.only(span.clone(), kind, &fields, &["corner", "u", "v"])?;
+                let corner = self.required(raw.corner, span.clone(), kind, "corner")?;
+                let u = self.required(raw.u, span.clone(), kind, "u")?;
+                let v = self.required(raw.v, span.clone(), kind, "v")?;
+                self.check(Some(&v), |&v| u.cross(v).length_square() > 0.0, "`u` and `v` must not be parallel")?;
+                let v = v.into_inner();
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Quad {
+                    corner,
+                    u,
+                    v,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "disk" => {
+                self.only(span.clone(), kind, &fields, &["center", "normal", "radius"])?;
+                let center = self.required(raw.center, span.clone(), kind, "center")?;
+                let normal = self.direction(raw.normal, span.clone(), kind, "normal")?;
+                let radius = self.required(raw.radius, span.clone(), kind, "radius")?;
+                let radius = self.positive(radius, "radius")?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Disk {
+                    center,
+                    normal,
+                    radius,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "box" => {
+                self.only(span.clone(), kind, &fields, &["min", "max"])?;
+                let min = self.required(raw.min, span.clone(), kind, "min")?;
+                let max = self.required(raw.max, span.clone(), kind, "max")?;
+                self.check(Some(&max), |max| (0..3).all(|axis| min[axis] < max[axis]), "`min` must be below `max` on every axis")?;
+                let max = max.into_inner();
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Box {
+                    min,
+                    max,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "cylinder" | "cone" => {
+                let end = if kind == "cylinder" { "top" } else { "apex" };
+                self.only(span.clone(), kind, &fields, &["base", end, "radius", "capped"])?;
+                let base = self.required(raw.base, span.clone(), kind, "base")?;
+                let end_point = self.required(if kind == "cylinder" { raw.top } else { raw.apex }, span.clone(), kind, end)?;
+                self.check(Some(&end_point), |&end_point| (end_point - base).length_square() > 0.0, &format!("`base` and `{}` must differ", end))?;
+                let end_point = end_point.into_inner();
+                let radius = self.required(raw.radius, span.clone(), kind, "radius")?;
+                let radius = self.positive(radius, "radius")?;
+                let capped = raw.capped.unwrap_or(true);
+                let material = self.material_name(self.required(raw.material, span, kind, "material")?, materials)?;
+                if kind == "cylinder" {
+                    return Ok(ShapeSpec::Cylinder { base, top: end_point, radius, capped, material });
+                }
+                Ok(ShapeSpec::Cone { base, apex: end_point, radius, capped, material })
+            }
+            "torus" => {
+                self.only(span.clone(), kind, &fields, &["center", "normal", "major_radius", "minor_radius"])?;
+                let center = self.required(raw.center, span.clone(), kind, "center")?;
+                let normal = match raw.normal {
+                    Some(_) => self.direction(raw.normal, span.clone(), kind, "normal")?,
+                    None => Vec3::new(0.0, 1.0, 0.0)
+                };
+                let major_radius = self.required(raw.major_radius, span.clone(), kind, "major_radius")?;
+                let major_radius = self.positive(major_radius, "major_radius")?;
+                let minor_radius = self.required(raw.minor_radius, span.clone(), kind, "minor_radius")?;
+                let minor_radius = self.positive(minor_radius, "minor_radius")?;
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Torus {
+                    center,
+                    normal,
+                    major_radius,
+                    minor_radius,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "quadric" => {
+                self.only(span.clone(), kind, &fields, &["coefficients", "min", "max"])?;
+                let coefficients = self.required(raw.coefficients, span.clone(), kind, "coefficients")?;
+                let min = self.required(raw.min, span.clone(), kind, "min")?;
+                let max = self.required(raw.max, span.clone(), kind, "max")?;
+                self.check(Some(&max), |max| (0..3).all(|axis| min[axis] <= max[axis]), "`min` must not be above `max` on any axis")?;
+                let max = max.into_inner();
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Quadric {
+                    coefficients,
+                    min,
+                    max,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            "mesh" => {
+                self.only(span.clone(), kind, &fields, &["path"])?;
+                let path = self.required(raw.path, span, kind, "path")?;
+                let material = match raw.material {
+                    Some(material) => Some(self.material_name(material, materials)?),
+                    None => None
+                };
+                Ok(ShapeSpec::Mesh {
+                    path: PathBuf::from(path),
+                    material,
+                    displacement: None,
+                })
+            }
+            "volume" => {
+                self.only(span.clone(), kind, &fields, &["path", "min", "max", "resolution", "emission"])?;
+                let path = self.required(raw.path, span.clone(), kind, "path")?;
+                let min = self.required(raw.min, span.clone(), kind, "min")?;
+                let max = self.required(raw.max, span.clone(), kind, "max")?;
+                self.check(Some(&max), |max| (0..3).all(|axis| min[axis] < max[axis]), "`min` must be below `max` on every axis")?;
+                let max = max.into_inner();
+                self.check(raw.resolution.as_ref(), |resolution| !resolution.contains(&0), "`resolution` must be positive")?;
+                self.check(raw.emission.as_ref(), |&emission| emission >= 0.0, "`emission` must not be negative")?;
+                let emission = inner(raw.emission).unwrap_or(1.0);
+                let material = self.required(raw.material, span, kind, "material")?;
+                Ok(ShapeSpec::Volume {
+                    path: PathBuf::from(path),
+                    min,
+                    max,
+                    resolution: inner(raw.resolution),
+                    emission,
+                    material: self.material_name(material, materials)?,
+                })
+            }
+            _ => Err(self.error(raw.kind.span(), format!("unknown object type `{}`", kind)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    const MALFORMED: &str = "[image]
+aspect_ratio = 1.5
+width = 60
+samples_per_pixel = 4
+max_depth = 8
+
+[camera]
+look_from = [0.0, 0.0, 1.0]
+look_at = [0.0, 0.0, 0.0]
+vfov = 40.0
+
+[[objects]]
+type = \"teapot\"
+";
+
+    fn location(source: &str) -> (usize, usize) {
+        match parse(source) {
+            Err(SceneError::Parse { line, column, .. }) => (line, column),
+            Err(err) => panic!("expected a parse error, got {}", err),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_value() {
+        assert_eq!(location(MALFORMED), (13, 8));
+        assert_eq!(location(&MALFORMED.replace("width = 60", "width = -60")), (3, 9));
+        assert_eq!(location(&MALFORMED.replace("vfov = 40.0", "vfov = 200.0")), (10, 8));
+        assert_eq!(location(&MALFORMED.replace("vfov = 40.0", "vfov = 40.0\nvup = [0.0, 0.0, -2.0]")), (11, 7));
+        assert_eq!(location(&MALFORMED.replace("look_from = [0.0, 0.0, 1.0]", "look_from = [0.0, 3.0, 0.0]")), (9, 11));
+        let sphere = "type = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 0.0\nmaterial = \"clay\"";
+        assert_eq!(location(&MALFORMED.replace("type = \"teapot\"", sphere)), (15, 10));
+        assert_eq!(location(&MALFORMED.replace("max_depth = 8", "max_depth = =")), (5, 13));
+    }
+
+    #[test]
+    fn saved_scenes_load_back_unchanged() {
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/../scenes/three-spheres.toml");
+        for scene in &[Scene::cornell_box(), Scene::random_spheres(), Scene::load(source).unwrap()] {
+            let path = env::temp_dir().join(format!("leonardo-roundtrip-{}.toml", std::process::id()));
+            scene.save(&path).unwrap();
+            let loaded = Scene::load(&path);
+            fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap().to_toml().unwrap(), scene.to_toml().unwrap());
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::algebra::utility::{random_f32, random_in_range_f32};
+use crate::algebra::vec3::{Color, Point3, Vec3};
+use crate::scene::{BackgroundSpec, CameraSettings, ImageSettings, MaterialSpec, ObjectSpec, Scene, ShapeSpec};
+
+fn sphere(center: Point3, radius: f32, material: &str) -> ObjectSpec {
+    ObjectSpec::new(ShapeSpec::Sphere {
+        center,
+        radius,
+        material: material.to_string(),
+    })
+}
+
+fn quad(corner: Point3, u: Vec3, v: Vec3, material: &str) -> ObjectSpec {
+    ObjectSpec::new(ShapeSpec::Quad {
+        corner,
+        u,
+        v,
+        material: material.to_string(),
+    })
+}
+
+pub fn random_spheres() -> Scene {
+    let mut scene = Scene::new(
+        ImageSettings::default(),
+        CameraSettings {
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: 0.0,
+            focus_distance: None,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        },
+    );
+    scene.materials.insert("ground".to_string(), MaterialSpec::Lambertian { albedo: Color::new(0.8, 0.8, 0.0).into() });
+    scene.materials.insert("glass".to_string(), MaterialSpec::Dielectric { ref_idx: Some(1.5), dispersion: None, roughness: 0.0, anisotropy: 0.0 });
+    scene.materials.insert("diffuse".to_string(), MaterialSpec::Lambertian { albedo: Color::new(0.4, 0.2, 0.1).into() });
+    scene.materials.insert("metal".to_string(), MaterialSpec::Metal { albedo: Color::new(0.7, 0.6, 0.5).into(), fuzz: 0.0 });
+    scene.objects.push(ObjectSpec::new(ShapeSpec::Plane {
+        point: Point3::new(0.0, 0.0, 0.0),
+        normal: Vec3::new(0.0, 1.0, 0.0),
+        material: "ground".to_string(),
+    }));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = random_f32();
+            let center = Point3::new(
+                (random_f32() * 0.9) + a as f32,
+                0.2,
+                (random_f32() * 0.9) + b as f32,
+            );
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.95 {
+                    let name = format!("sphere_{}_{}", a, b);
+                    let material = if choose_mat < 0.8 {
+                        MaterialSpec::Lambertian { albedo: (Color::random() * Color::random()).into() }
+                    } else {
+                        let albedo = Color::random_in_range(0.5, 1.0);
+                        MaterialSpec::Metal { albedo: albedo.into(), fuzz: random_in_range_f32(0.0, 0.5) }
+                    };
+                    scene.materials.insert(name.clone(), material);
+                    scene.objects.push(sphere(center, 0.2, &name));
+                } else {
+                    scene.objects.push(sphere(center, 0.2, "glass"));
+                }
+            }
+        }
+    }
+
+    scene.objects.push(sphere(Point3::new(0.0, 1.0, 0.0), 1.0, "glass"));
+    scene.objects.push(sphere(Point3::new(-4.0, 1.0, 0.0), 1.0, "diffuse"));
+    scene.objects.push(sphere(Point3::new(4.0, 1.0, 0.0), 1.0, "metal"));
+    scene
+}
+
+pub fn cornell_box() -> Scene {
+    let mut scene = Scene::new(
+        ImageSettings {
+            aspect_ratio: 1.0,
+            width: 300,
+            samples_per_pixel: 200,
+            max_depth: 50,
+            spectral: false,
+        },
+        CameraSettings {
+            look_from: Point3::new(278.0, 278.0, 800.0),
+            look_at: Point3::new(278.0, 278.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_distance: None,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        },
+    );
+    scene.background = BackgroundSpec::Solid { color: Color::new(0.0, 0.0, 0.0) };
+    scene.materials.insert("red".to_string(), MaterialSpec::Lambertian { albedo: Color::new(0.65, 0.05, 0.05).into() });
+    scene.materials.insert("white".to_string(), MaterialSpec::Lambertian { albedo: Color::new(0.73, 0.73, 0.73).into() });
+    scene.materials.insert("green".to_string(), MaterialSpec::Lambertian { albedo: Color::new(0.12, 0.45, 0.15).into() });
+    scene.materials.insert("light".to_string(), MaterialSpec::DiffuseLight { emit: Color::new(15.0, 15.0, 15.0).into(), two_sided: false });
+    scene.materials.insert("glass".to_string(), MaterialSpec::Dielectric { ref_idx: Some(1.5), dispersion: None, roughness: 0.0, anisotropy: 0.0 });
+    scene.materials.insert("metal".to_string(), MaterialSpec::Metal { albedo: Color::new(0.8, 0.85, 0.88).into(), fuzz: 0.0 });
+
+    let objects = &mut scene.objects;
+    objects.push(quad(Point3::new(0.0, 0.0, -555.0), Vec3::new(0.0, 0.0, 555.0), Vec3::new(0.0, 555.0, 0.0), "red"));
+    objects.push(quad(Point3::new(555.0, 0.0, -555.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), "green"));
+    objects.push(quad(Point3::new(0.0, 0.0, -555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), "white"));
+    objects.push(quad(Point3::new(0.0, 555.0, -555.0), Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), "white"));
+    objects.push(quad(Point3::new(0.0, 0.0, -555.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(555.0, 0.0, 0.0), "white"));
+    objects.push(quad(Point3::new(213.0, 554.0, -332.0), Vec3::new(130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 105.0), "light"));
+    objects.push(sphere(Point3::new(190.0, 90.0, -370.0), 90.0, "glass"));
+    objects.push(sphere(Point3::new(370.0, 90.0, -190.0), 90.0, "metal"));
+    scene
+}
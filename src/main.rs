@@ -1,10 +1,223 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+
+use leonardo_engine::algebra::utility::seed_rng;
+use leonardo_engine::graphics::image::ImageFormat;
+use leonardo_engine::graphics::tonemap::{DisplayTransform, ToneMapOperator, TransferFunction};
+use leonardo_engine::scene::{Scene, SceneError};
 use leonardo_engine::App;
 
-fn main() -> std::io::Result<()> {
-    let aspect_ratio = 16.0 / 9.0_f32;
-    let image_width = 384;
-    let samples_per_pixel = 100;
-    let max_depth = 50;
-    let app = App::new(aspect_ratio, image_width, samples_per_pixel, max_depth);
-    app.run()
-}
\ No newline at end of file
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Preset {
+    RandomSpheres,
+    CornellBox,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Format {
+    /// 8-bit PNG
+    Png,
+    /// 16-bit PNG
+    Png16,
+    /// ASCII PPM (P3), the format `.ppm` files get by default
+    #[value(alias = "ppm-ascii")]
+    Ppm,
+    /// Binary PPM (P6)
+    PpmBinary,
+    /// OpenEXR with 32-bit float channels
+    Exr,
+    /// OpenEXR with 16-bit half float channels
+    ExrHalf,
+    /// Radiance RGBE
+    Hdr,
+}
+
+impl Format {
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Format::Png => ImageFormat::Png8,
+            Format::Png16 => ImageFormat::Png16,
+            Format::Ppm => ImageFormat::PpmAscii,
+            Format::PpmBinary => ImageFormat::PpmBinary,
+            Format::Exr => ImageFormat::ExrFloat,
+            Format::ExrHalf => ImageFormat::ExrHalf,
+            Format::Hdr => ImageFormat::Hdr,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ToneMap {
+    /// Clip values above 1
+    Clamp,
+    /// Reinhard on luminance
+    Reinhard,
+    /// Reinhard on luminance with a white point (see --white-point)
+    ReinhardExtended,
+    /// Hable / Uncharted 2 filmic curve
+    Hable,
+    /// ACES filmic curve fitted by Stephen Hill
+    Aces,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Transfer {
+    /// Piecewise sRGB transfer function
+    Srgb,
+    /// Square-root gamma of earlier releases
+    Gamma2,
+    /// No transfer function
+    Linear,
+}
+
+/// Render a scene with the leonardo ray tracing engine.
+#[derive(Parser, Debug)]
+#[command(name = "rust-ray", version)]
+struct Cli {
+    /// Scene description file (TOML); defaults to the random-spheres preset
+    #[arg(value_name = "SCENE", conflicts_with = "preset")]
+    scene: Option<PathBuf>,
+
+    /// Render a built-in scene instead of a scene file
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+
+    /// Output image path
+    #[arg(short, long, value_name = "FILE", default_value = "ray.ppm")]
+    output: PathBuf,
+
+    /// Output image format (defaults to the one matching the output extension; `.ppm` files are written as
+    /// ASCII P3 unless `--format ppm-binary` asks for P6)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Image width in pixels
+    #[arg(long, value_parser = clap::value_parser!(i32).range(2..))]
+    width: Option<i32>,
+
+    /// Image height in pixels (defaults to width / aspect ratio)
+    #[arg(long, value_parser = clap::value_parser!(i32).range(2..), requires = "width")]
+    height: Option<i32>,
+
+    /// Samples per pixel
+    #[arg(short, long, value_parser = clap::value_parser!(i32).range(1..))]
+    spp: Option<i32>,
+
+    /// Maximum number of ray bounces
+    #[arg(short = 'd', long, value_parser = clap::value_parser!(i32).range(1..))]
+    max_depth: Option<i32>,
+
+    /// Number of render threads (defaults to the number of CPUs)
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Trace hero wavelengths instead of RGB so dispersive glass splits light
+    #[arg(long)]
+    spectral: bool,
+
+    /// Seed for the random number generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Exposure compensation in stops, applied before tone mapping
+    #[arg(long, value_name = "EV", default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Tone mapping operator for PNG and PPM output
+    #[arg(long, value_enum, default_value = "clamp")]
+    tonemap: ToneMap,
+
+    /// Luminance mapped to white by reinhard-extended
+    #[arg(long, default_value_t = 4.0)]
+    white_point: f32,
+
+    /// Transfer function for PNG and PPM output
+    #[arg(long, value_enum, default_value = "srgb")]
+    transfer: Transfer,
+
+    /// Write the scene description to a TOML file before rendering
+    #[arg(long, value_name = "FILE")]
+    save_scene: Option<PathBuf>,
+}
+
+fn display_transform(cli: &Cli) -> DisplayTransform {
+    let operator = match cli.tonemap {
+        ToneMap::Clamp => ToneMapOperator::Clamp,
+        ToneMap::Reinhard => ToneMapOperator::Reinhard,
+        ToneMap::ReinhardExtended => ToneMapOperator::ReinhardExtended { white_point: cli.white_point },
+        ToneMap::Hable => ToneMapOperator::Hable,
+        ToneMap::Aces => ToneMapOperator::AcesFitted,
+    };
+    let transfer = match cli.transfer {
+        Transfer::Srgb => TransferFunction::Srgb,
+        Transfer::Gamma2 => TransferFunction::Gamma2,
+        Transfer::Linear => TransferFunction::Linear,
+    };
+    DisplayTransform::new(cli.exposure, operator, transfer)
+}
+
+fn load_scene(cli: &Cli) -> Result<Scene, String> {
+    match (&cli.scene, cli.preset) {
+        (Some(path), _) => Scene::load(path).map_err(|err| match err {
+            SceneError::Parse { .. } => format!("{}: {}", path.display(), err),
+            _ => err.to_string()
+        }),
+        (None, Some(Preset::RandomSpheres)) | (None, None) => {
+            seed_rng(cli.seed);
+            Ok(Scene::random_spheres())
+        }
+        (None, Some(Preset::CornellBox)) => Ok(Scene::cornell_box()),
+    }
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let format = match cli.format {
+        Some(format) => format.image_format(),
+        None => ImageFormat::from_path(&cli.output).ok_or_else(|| format!(
+            "cannot infer an image format from {}; use --format",
+            cli.output.display(),
+        ))?
+    };
+    let scene = load_scene(&cli)?;
+    if let Some(path) = &cli.save_scene {
+        scene.save(path).map_err(|err| err.to_string())?;
+    }
+
+    let aspect_ratio = scene.image.aspect_ratio;
+    let mut app = App::from_scene(scene);
+    if let Some(width) = cli.width {
+        let height = cli.height.unwrap_or((width as f32 / aspect_ratio) as i32);
+        app = app.with_resolution(width, height.max(2));
+    }
+    if let Some(spp) = cli.spp {
+        app = app.with_samples_per_pixel(spp);
+    }
+    if let Some(max_depth) = cli.max_depth {
+        app = app.with_max_depth(max_depth);
+    }
+    if let Some(threads) = cli.threads {
+        app = app.with_threads(threads as usize);
+    }
+    if cli.spectral {
+        app = app.with_spectral(true);
+    }
+    app.with_seed(cli.seed)
+        .with_output(&cli.output)
+        .with_format(format)
+        .with_display_transform(display_transform(&cli))
+        .run()
+        .map_err(|err| err.to_string())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("rust-ray: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}